@@ -0,0 +1,20 @@
+#![no_main]
+
+use bson2::{Document, DocumentRef, ExtendedJsonMode};
+use libfuzzer_sys::fuzz_target;
+
+// every decoder may reject the input, none of them may panic
+fuzz_target!(|data: &[u8]| {
+    let _ = Document::validate(data);
+    if let Ok(doc) = Document::try_from(data) {
+        for (key, _) in doc.iter() {
+            let _ = doc.get_any(&key);
+        }
+        let _ = doc.to_extended_json(ExtendedJsonMode::Relaxed);
+    }
+    if let Ok(doc) = DocumentRef::try_from(data) {
+        for (key, _) in doc.iter() {
+            let _ = doc.get_any(key);
+        }
+    }
+});
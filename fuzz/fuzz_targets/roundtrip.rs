@@ -0,0 +1,18 @@
+#![no_main]
+
+use bson2::{Document, DocumentBuilder};
+use libfuzzer_sys::fuzz_target;
+
+// a document that passes validation must encode back to the same bytes
+fuzz_target!(|data: &[u8]| {
+    let doc = match Document::try_from_validated(data) {
+        Ok(doc) => doc,
+        Err(_) => return,
+    };
+    let mut builder = DocumentBuilder::new();
+    for (key, element) in doc.iter() {
+        builder.append(&key, &element);
+    }
+    assert_eq!(doc, builder.build());
+    assert_eq!(data, doc.to_bytes().as_slice());
+});
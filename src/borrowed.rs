@@ -0,0 +1,386 @@
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+use super::timestamp::*;
+
+pub type KeyPairRef<'a> = (&'a str, ElementRef<'a>);
+
+/// Borrowed document body, same layout as [`Document::data`] without copying it
+///
+/// ```rust
+/// use bson2::{doc, DocumentRef};
+///
+/// let doc = doc! { "name": "bson", "nested": { "list": [1, 2] } };
+/// let doc_ref = DocumentRef::from(&doc);
+/// assert_eq!(Ok("bson"), doc_ref.get_str("name"));
+/// let list = doc_ref.get_document("nested").unwrap().get_array("list").unwrap();
+/// assert_eq!(Ok(2), list.get_int32(1));
+/// assert_eq!(doc, doc_ref.to_owned());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DocumentRef<'a> {
+    pub data: &'a [u8],
+}
+
+/// Borrowed array body, keys are "0", "1", ...
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArrayRef<'a> {
+    pub data: &'a [u8],
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct BinaryRef<'a> {
+    pub binary_type: BinaryType,
+    pub data: &'a [u8],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DbPointerRef<'a> {
+    pub namespace: &'a str,
+    pub id: ObjectId,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CodeWithScopeRef<'a> {
+    pub code: &'a str,
+    pub scope: DocumentRef<'a>,
+}
+
+/// Borrowed counterpart of [`Element`], strings and sub documents point into the source bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElementRef<'a> {
+    Double(f64),
+    String(&'a str),
+    EmbededDocument(DocumentRef<'a>),
+    ArrayDocument(ArrayRef<'a>),
+    Binary(BinaryRef<'a>),
+    Undefined,
+    ObjectId(ObjectId),
+    Boolean(bool),
+    DateTime(DateTime),
+    Null,
+    RegularExpression { pattern: &'a str, options: &'a str },
+    DbPointer(DbPointerRef<'a>),
+    Javascript(&'a str),
+    Symbol(&'a str),
+    JavascriptCode(CodeWithScopeRef<'a>),
+    Int32(i32),
+    Timestamp(Timestamp),
+    Int64(i64),
+    Decimal(Decimal128),
+    Min,
+    Max,
+}
+
+impl<'a> std::fmt::Debug for BinaryRef<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        debug_binary(f, "BinaryRef", self.binary_type, self.data)
+    }
+}
+
+impl<'a> BinaryRef<'a> {
+    pub fn to_owned(&self) -> Binary {
+        Binary {
+            binary_type: self.binary_type,
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl<'a> DbPointerRef<'a> {
+    pub fn to_owned(&self) -> DbPointer {
+        DbPointer {
+            namespace: self.namespace.to_string(),
+            id: self.id,
+        }
+    }
+}
+
+impl<'a> CodeWithScopeRef<'a> {
+    pub fn to_owned(&self) -> CodeWithScope {
+        CodeWithScope {
+            code: self.code.to_string(),
+            scope: self.scope.to_owned(),
+        }
+    }
+}
+
+impl<'a> ElementRef<'a> {
+    /// bson type byte of the value
+    pub fn element_type(&self) -> u8 {
+        match self {
+            ElementRef::Double(_) => ELEMENT_TYPE_DOUBLE,
+            ElementRef::String(_) => ELEMENT_TYPE_STRING,
+            ElementRef::EmbededDocument(_) => ELEMENT_TYPE_EMBED_DOCUMENT,
+            ElementRef::ArrayDocument(_) => ELEMENT_TYPE_ARRAY_DOCUMENT,
+            ElementRef::Binary(_) => ELEMENT_TYPE_BINARY,
+            ElementRef::Undefined => ELEMENT_TYPE_UNDEFINED,
+            ElementRef::ObjectId(_) => ELEMENT_TYPE_OBJECT_ID,
+            ElementRef::Boolean(_) => ELEMENT_TYPE_BOOLEAN,
+            ElementRef::DateTime(_) => ELEMENT_TYPE_DATETIME,
+            ElementRef::Null => ELEMENT_TYPE_NULL,
+            ElementRef::RegularExpression { .. } => ELEMENT_TYPE_REGULAR_EXPRESSION,
+            ElementRef::DbPointer(_) => ELEMENT_TYPE_DBPOINTER,
+            ElementRef::Javascript(_) => ELEMENT_TYPE_JAVASCRIPTCODE,
+            ElementRef::Symbol(_) => ELEMENT_TYPE_SYMBOL,
+            ElementRef::JavascriptCode(_) => ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE,
+            ElementRef::Int32(_) => ELEMENT_TYPE_INT32,
+            ElementRef::Timestamp(_) => ELEMENT_TYPE_TIMESTAMP,
+            ElementRef::Int64(_) => ELEMENT_TYPE_INT64,
+            ElementRef::Decimal(_) => ELEMENT_TYPE_DECIMAL128,
+            ElementRef::Min => ELEMENT_TYPE_MIN,
+            ElementRef::Max => ELEMENT_TYPE_MAX,
+        }
+    }
+
+    /// copies the value into an owned [`Element`]
+    pub fn to_owned(&self) -> Element {
+        match *self {
+            ElementRef::Double(value) => Element::Double(value),
+            ElementRef::String(value) => Element::String(value.to_string()),
+            ElementRef::EmbededDocument(doc) => Element::EmbededDocument(doc.to_owned()),
+            ElementRef::ArrayDocument(array) => Element::ArrayDocument(array.to_owned()),
+            ElementRef::Binary(binary) => Element::Binary(binary.to_owned()),
+            ElementRef::Undefined => Element::Undefined,
+            ElementRef::ObjectId(id) => Element::ObjectId(id),
+            ElementRef::Boolean(value) => Element::Boolean(value),
+            ElementRef::DateTime(value) => Element::DateTime(value),
+            ElementRef::Null => Element::Null,
+            ElementRef::RegularExpression { pattern, options } => Element::RegularExpression {
+                pattern: pattern.to_string(),
+                options: options.to_string(),
+            },
+            ElementRef::DbPointer(pointer) => Element::DbPointer(pointer.to_owned()),
+            ElementRef::Javascript(code) => Element::Javascript(code.to_string()),
+            ElementRef::Symbol(symbol) => Element::Symbol(symbol.to_string()),
+            ElementRef::JavascriptCode(code) => Element::JavascriptCode(code.to_owned()),
+            ElementRef::Int32(value) => Element::Int32(value),
+            ElementRef::Timestamp(value) => Element::Timestamp(value),
+            ElementRef::Int64(value) => Element::Int64(value),
+            ElementRef::Decimal(value) => Element::Decimal(value),
+            ElementRef::Min => Element::Min,
+            ElementRef::Max => Element::Max,
+        }
+    }
+}
+
+fn find_value_ref<'a>(
+    input: &'a [u8],
+    matches: impl Fn(&str) -> bool,
+) -> Result<Option<ElementRef<'a>>, BsonError> {
+    let mut mut_input = input;
+    while !mut_input.is_empty() {
+        let (input1, (ename, element)) = decode_element(input, mut_input)?;
+        mut_input = input1;
+        if matches(ename) {
+            return Ok(Some(element));
+        }
+    }
+    Ok(None)
+}
+
+/// compares an array key with `index` without formatting the index
+fn is_index(key: &str, index: usize) -> bool {
+    key.bytes().all(|b| b.is_ascii_digit())
+        && (key.len() == 1 || !key.starts_with('0'))
+        && key.parse() == Ok(index)
+}
+
+macro_rules! match_element_ref {
+    ($func_name:ident, $key:ty, $type:ty, $element_type:path, $type_byte:expr) => {
+        pub fn $func_name(&self, key: $key) -> Result<$type, BsonError> {
+            match self.get_any(key)? {
+                $element_type(res) => Ok(res),
+                other => Err(BsonError::type_mismatch($type_byte, other.element_type())
+                    .with_key(&key.to_string())),
+            }
+        }
+    };
+}
+
+macro_rules! getters_ref {
+    ($key:ty) => {
+        match_element_ref!(
+            get_float,
+            $key,
+            f64,
+            ElementRef::Double,
+            ELEMENT_TYPE_DOUBLE
+        );
+        match_element_ref!(
+            get_str,
+            $key,
+            &'a str,
+            ElementRef::String,
+            ELEMENT_TYPE_STRING
+        );
+        match_element_ref!(
+            get_document,
+            $key,
+            DocumentRef<'a>,
+            ElementRef::EmbededDocument,
+            ELEMENT_TYPE_EMBED_DOCUMENT
+        );
+        match_element_ref!(
+            get_array,
+            $key,
+            ArrayRef<'a>,
+            ElementRef::ArrayDocument,
+            ELEMENT_TYPE_ARRAY_DOCUMENT
+        );
+        match_element_ref!(
+            get_binary,
+            $key,
+            BinaryRef<'a>,
+            ElementRef::Binary,
+            ELEMENT_TYPE_BINARY
+        );
+        match_element_ref!(
+            get_object_id,
+            $key,
+            ObjectId,
+            ElementRef::ObjectId,
+            ELEMENT_TYPE_OBJECT_ID
+        );
+        match_element_ref!(
+            get_bool,
+            $key,
+            bool,
+            ElementRef::Boolean,
+            ELEMENT_TYPE_BOOLEAN
+        );
+        match_element_ref!(
+            get_datetime,
+            $key,
+            DateTime,
+            ElementRef::DateTime,
+            ELEMENT_TYPE_DATETIME
+        );
+        match_element_ref!(
+            get_dbpointer,
+            $key,
+            DbPointerRef<'a>,
+            ElementRef::DbPointer,
+            ELEMENT_TYPE_DBPOINTER
+        );
+        match_element_ref!(
+            get_javascript,
+            $key,
+            &'a str,
+            ElementRef::Javascript,
+            ELEMENT_TYPE_JAVASCRIPTCODE
+        );
+        match_element_ref!(
+            get_code_with_scope,
+            $key,
+            CodeWithScopeRef<'a>,
+            ElementRef::JavascriptCode,
+            ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE
+        );
+        match_element_ref!(
+            get_symbol,
+            $key,
+            &'a str,
+            ElementRef::Symbol,
+            ELEMENT_TYPE_SYMBOL
+        );
+        match_element_ref!(get_int32, $key, i32, ElementRef::Int32, ELEMENT_TYPE_INT32);
+        match_element_ref!(
+            get_timestamp,
+            $key,
+            Timestamp,
+            ElementRef::Timestamp,
+            ELEMENT_TYPE_TIMESTAMP
+        );
+        match_element_ref!(get_i64, $key, i64, ElementRef::Int64, ELEMENT_TYPE_INT64);
+        match_element_ref!(
+            get_decimal128,
+            $key,
+            Decimal128,
+            ElementRef::Decimal,
+            ELEMENT_TYPE_DECIMAL128
+        );
+
+        pub fn is_null(&self, key: $key) -> Result<bool, BsonError> {
+            Ok(matches!(self.get_any(key)?, ElementRef::Null))
+        }
+
+        pub fn iter(&self) -> DocumentRefIter<'a> {
+            DocumentRefIter { doc: self.data }
+        }
+    };
+}
+
+impl<'a> DocumentRef<'a> {
+    pub fn get_any(&self, key: &str) -> Result<ElementRef<'a>, BsonError> {
+        find_value_ref(self.data, |ename| ename == key)?
+            .ok_or_else(|| BsonError::new(ErrorKind::KeyNotFound).with_key(key))
+    }
+
+    getters_ref!(&str);
+
+    pub fn to_owned(&self) -> Document {
+        Document {
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl<'a> ArrayRef<'a> {
+    pub fn get_any(&self, index: usize) -> Result<ElementRef<'a>, BsonError> {
+        find_value_ref(self.data, |ename| is_index(ename, index))?
+            .ok_or_else(|| BsonError::new(ErrorKind::KeyNotFound).with_key(&index.to_string()))
+    }
+
+    getters_ref!(usize);
+
+    pub fn to_owned(&self) -> Array {
+        Array {
+            data: self.data.to_vec(),
+        }
+    }
+}
+
+impl<'a> From<&'a Document> for DocumentRef<'a> {
+    fn from(doc: &'a Document) -> Self {
+        DocumentRef { data: &doc.data }
+    }
+}
+
+impl<'a> From<&'a Array> for ArrayRef<'a> {
+    fn from(array: &'a Array) -> Self {
+        ArrayRef { data: &array.data }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for DocumentRef<'a> {
+    type Error = BsonError;
+
+    fn try_from(input: &'a [u8]) -> Result<Self, Self::Error> {
+        let (_input, doc) = parse_document_ref(input).map_err(|err| parse_error(input, err, 0))?;
+        Ok(doc)
+    }
+}
+
+pub struct DocumentRefIter<'a> {
+    doc: &'a [u8],
+}
+
+impl<'a> Iterator for DocumentRefIter<'a> {
+    type Item = KeyPairRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.doc.is_empty() {
+            return None;
+        }
+        match parse_any_ref(self.doc) {
+            Ok((input, pair)) => {
+                self.doc = input;
+                Some(pair)
+            }
+            Err(_) => None,
+        }
+    }
+}
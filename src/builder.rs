@@ -0,0 +1,336 @@
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::encode::*;
+use super::error::*;
+use super::parse::*;
+use super::timestamp::*;
+
+/// Builds a [`Document`] field by field directly in its raw form
+///
+/// ```rust
+/// use bson2::DocumentBuilder;
+///
+/// let mut builder = DocumentBuilder::new();
+/// builder.append_string("hi", "hi").append_int32("count", 1);
+/// let mut array = builder.start_array("list");
+/// array.append_bool(true).append_double(1.0);
+/// array.end();
+/// let doc = builder.build();
+/// assert_eq!(Ok("hi".to_string()), doc.get_string("hi"));
+/// assert_eq!(Ok(1.0), doc.get_array("list").unwrap().get_float(1));
+/// ```
+#[derive(Debug, Default)]
+pub struct DocumentBuilder {
+    data: Vec<u8>,
+}
+
+/// Builds an [`Array`], numbering keys "0", "1", ... automatically
+#[derive(Debug, Default)]
+pub struct ArrayBuilder {
+    data: Vec<u8>,
+    index: usize,
+}
+
+/// Document nested inside another builder, length prefix is back-patched on `end` or drop
+#[derive(Debug)]
+pub struct NestedDocumentBuilder<'a> {
+    data: &'a mut Vec<u8>,
+    start: usize,
+}
+
+/// Array nested inside another builder, length prefix is back-patched on `end` or drop
+#[derive(Debug)]
+pub struct NestedArrayBuilder<'a> {
+    data: &'a mut Vec<u8>,
+    start: usize,
+    index: usize,
+}
+
+fn start_element<'a>(data: &'a mut Vec<u8>, element_type: u8, key: &str) -> &'a mut Vec<u8> {
+    data.push(element_type);
+    encode_cstring(data, key);
+    data
+}
+
+/// writes a placeholder length prefix and returns its position
+fn start_nested(data: &mut Vec<u8>) -> usize {
+    let start = data.len();
+    encode_i32(data, 0);
+    start
+}
+
+fn end_nested(data: &mut Vec<u8>, start: usize) {
+    data.push(0);
+    let size = (data.len() - start) as i32;
+    data[start..start + 4].copy_from_slice(&size.to_le_bytes());
+}
+
+macro_rules! append_value {
+    (document, $func_name:ident, $type:ty, $element_type:expr, $encode:expr) => {
+        pub fn $func_name(&mut self, key: &str, value: $type) -> &mut Self {
+            let buf = self.start_element($element_type, key);
+            $encode(buf, value);
+            self
+        }
+    };
+    (array, $func_name:ident, $type:ty, $element_type:expr, $encode:expr) => {
+        pub fn $func_name(&mut self, value: $type) -> &mut Self {
+            let buf = self.next_element($element_type);
+            $encode(buf, value);
+            self
+        }
+    };
+}
+
+macro_rules! append_unit {
+    (document, $func_name:ident, $element_type:expr) => {
+        pub fn $func_name(&mut self, key: &str) -> &mut Self {
+            self.start_element($element_type, key);
+            self
+        }
+    };
+    (array, $func_name:ident, $element_type:expr) => {
+        pub fn $func_name(&mut self) -> &mut Self {
+            self.next_element($element_type);
+            self
+        }
+    };
+}
+
+macro_rules! append_methods {
+    ($kind:ident) => {
+        append_value!(
+            $kind,
+            append_double,
+            f64,
+            ELEMENT_TYPE_DOUBLE,
+            encode_double
+        );
+        append_value!(
+            $kind,
+            append_string,
+            &str,
+            ELEMENT_TYPE_STRING,
+            encode_string
+        );
+        append_value!(
+            $kind,
+            append_document,
+            &Document,
+            ELEMENT_TYPE_EMBED_DOCUMENT,
+            |buf, doc: &Document| encode_document(buf, &doc.data)
+        );
+        append_value!(
+            $kind,
+            append_array,
+            &Array,
+            ELEMENT_TYPE_ARRAY_DOCUMENT,
+            |buf, arr: &Array| encode_document(buf, &arr.data)
+        );
+        append_value!(
+            $kind,
+            append_binary,
+            &Binary,
+            ELEMENT_TYPE_BINARY,
+            encode_binary
+        );
+        append_value!(
+            $kind,
+            append_object_id,
+            ObjectId,
+            ELEMENT_TYPE_OBJECT_ID,
+            encode_object_id
+        );
+        append_value!($kind, append_bool, bool, ELEMENT_TYPE_BOOLEAN, encode_bool);
+        append_value!(
+            $kind,
+            append_datetime,
+            DateTime,
+            ELEMENT_TYPE_DATETIME,
+            encode_datetime
+        );
+        append_value!(
+            $kind,
+            append_javascript,
+            &str,
+            ELEMENT_TYPE_JAVASCRIPTCODE,
+            encode_string
+        );
+        append_value!(
+            $kind,
+            append_symbol,
+            &str,
+            ELEMENT_TYPE_SYMBOL,
+            encode_string
+        );
+        append_value!($kind, append_int32, i32, ELEMENT_TYPE_INT32, encode_i32);
+        append_value!(
+            $kind,
+            append_timestamp,
+            Timestamp,
+            ELEMENT_TYPE_TIMESTAMP,
+            encode_timestamp
+        );
+        append_value!($kind, append_int64, i64, ELEMENT_TYPE_INT64, encode_i64);
+        append_value!(
+            $kind,
+            append_decimal128,
+            Decimal128,
+            ELEMENT_TYPE_DECIMAL128,
+            encode_decimal
+        );
+        append_unit!($kind, append_undefined, ELEMENT_TYPE_UNDEFINED);
+        append_unit!($kind, append_null, ELEMENT_TYPE_NULL);
+        append_unit!($kind, append_min, ELEMENT_TYPE_MIN);
+        append_unit!($kind, append_max, ELEMENT_TYPE_MAX);
+    };
+}
+
+macro_rules! document_methods {
+    () => {
+        fn start_element(&mut self, element_type: u8, key: &str) -> &mut Vec<u8> {
+            start_element(self.buf(), element_type, key)
+        }
+
+        append_methods!(document);
+
+        /// panics if `key` or a regular expression contains a null byte, which `try_append`
+        /// reports as an error instead
+        pub fn append(&mut self, key: &str, element: &Element) -> &mut Self {
+            let buf = self.start_element(element.element_type(), key);
+            element.encode_into(buf);
+            self
+        }
+
+        pub fn try_append(&mut self, key: &str, element: &Element) -> Result<&mut Self, BsonError> {
+            check_cstring(key)
+                .and_then(|_| element.check_cstrings())
+                .map_err(|error| error.with_key(key))?;
+            Ok(self.append(key, element))
+        }
+
+        pub fn start_document(&mut self, key: &str) -> NestedDocumentBuilder<'_> {
+            let data = self.start_element(ELEMENT_TYPE_EMBED_DOCUMENT, key);
+            let start = start_nested(data);
+            NestedDocumentBuilder { data, start }
+        }
+
+        pub fn start_array(&mut self, key: &str) -> NestedArrayBuilder<'_> {
+            let data = self.start_element(ELEMENT_TYPE_ARRAY_DOCUMENT, key);
+            let start = start_nested(data);
+            NestedArrayBuilder {
+                data,
+                start,
+                index: 0,
+            }
+        }
+    };
+}
+
+macro_rules! array_methods {
+    () => {
+        fn next_element(&mut self, element_type: u8) -> &mut Vec<u8> {
+            let key = self.index.to_string();
+            self.index += 1;
+            start_element(self.buf(), element_type, &key)
+        }
+
+        append_methods!(array);
+
+        /// panics on a regular expression containing a null byte, which `try_append` reports
+        /// as an error instead
+        pub fn append(&mut self, element: &Element) -> &mut Self {
+            let buf = self.next_element(element.element_type());
+            element.encode_into(buf);
+            self
+        }
+
+        pub fn try_append(&mut self, element: &Element) -> Result<&mut Self, BsonError> {
+            element.check_cstrings()?;
+            Ok(self.append(element))
+        }
+
+        pub fn start_document(&mut self) -> NestedDocumentBuilder<'_> {
+            let data = self.next_element(ELEMENT_TYPE_EMBED_DOCUMENT);
+            let start = start_nested(data);
+            NestedDocumentBuilder { data, start }
+        }
+
+        pub fn start_array(&mut self) -> NestedArrayBuilder<'_> {
+            let data = self.next_element(ELEMENT_TYPE_ARRAY_DOCUMENT);
+            let start = start_nested(data);
+            NestedArrayBuilder {
+                data,
+                start,
+                index: 0,
+            }
+        }
+    };
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn buf(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+
+    document_methods!();
+
+    pub fn build(self) -> Document {
+        Document { data: self.data }
+    }
+}
+
+impl ArrayBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn buf(&mut self) -> &mut Vec<u8> {
+        &mut self.data
+    }
+
+    array_methods!();
+
+    pub fn build(self) -> Array {
+        Array { data: self.data }
+    }
+}
+
+impl<'a> NestedDocumentBuilder<'a> {
+    fn buf(&mut self) -> &mut Vec<u8> {
+        self.data
+    }
+
+    document_methods!();
+
+    /// closes the nested document, same as dropping the builder
+    pub fn end(self) {}
+}
+
+impl<'a> Drop for NestedDocumentBuilder<'a> {
+    fn drop(&mut self) {
+        end_nested(self.data, self.start);
+    }
+}
+
+impl<'a> NestedArrayBuilder<'a> {
+    fn buf(&mut self) -> &mut Vec<u8> {
+        self.data
+    }
+
+    array_methods!();
+
+    /// closes the nested array, same as dropping the builder
+    pub fn end(self) {}
+}
+
+impl<'a> Drop for NestedArrayBuilder<'a> {
+    fn drop(&mut self) {
+        end_nested(self.data, self.start);
+    }
+}
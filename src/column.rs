@@ -0,0 +1,874 @@
+use std::collections::VecDeque;
+
+use super::builder::*;
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::encode::*;
+use super::error::*;
+use super::parse::*;
+use super::timestamp::*;
+
+/// (selector, extended selector, bits per value, values per block) of the Simple-8b layouts by
+/// decreasing count. Selectors 7 and 8 keep an extended selector in bits 4-7 and precede each
+/// value with a count of the trailing zero bits (7) or nibbles (8) dropped from it.
+const LAYOUTS: [(u64, u64, u32, usize); 28] = [
+    (1, 0, 1, 60),
+    (2, 0, 2, 30),
+    (3, 0, 3, 20),
+    (4, 0, 4, 15),
+    (5, 0, 5, 12),
+    (6, 0, 6, 10),
+    (7, 1, 2, 9),
+    (7, 2, 3, 8),
+    (7, 3, 4, 7),
+    (8, 1, 4, 7),
+    (9, 0, 10, 6),
+    (7, 4, 5, 6),
+    (8, 2, 5, 6),
+    (10, 0, 12, 5),
+    (7, 5, 7, 5),
+    (8, 3, 7, 5),
+    (11, 0, 15, 4),
+    (7, 6, 10, 4),
+    (8, 4, 10, 4),
+    (12, 0, 20, 3),
+    (7, 7, 14, 3),
+    (8, 5, 14, 3),
+    (13, 0, 30, 2),
+    (7, 8, 24, 2),
+    (8, 6, 24, 2),
+    (14, 0, 60, 1),
+    (7, 9, 52, 1),
+    (8, 7, 52, 1),
+];
+/// bits of the trailing zero count in front of the values of extended selectors
+const TRAILING_ZEROS_BITS: u32 = 4;
+const RLE_SELECTOR: u64 = 15;
+/// values a run length encoded block repeats per unit of its count
+const RLE_MULTIPLIER: usize = 120;
+/// blocks following one control byte
+const MAX_BLOCKS: usize = 16;
+
+/// multipliers turning doubles into integers, picked by the high nibble of the control byte
+const DOUBLE_SCALES: [f64; 5] = [1.0, 10.0, 100.0, 10_000.0, 100_000_000.0];
+/// deltas of the raw bits, the only scale of non double types
+const SCALE_NONE: usize = 5;
+
+/// control bytes starting interleaved mode, 0xF0 is the older form for objects and read like
+/// 0xF1, 0xF2 rebuilds arrays
+const INTERLEAVED_LEGACY: u8 = 0xF0;
+const INTERLEAVED: u8 = 0xF1;
+const INTERLEAVED_ARRAY: u8 = 0xF2;
+
+/// byte of the ObjectId stored in each byte of its delta encoding, timestamp and counter
+/// interleaved so that both grow from the low bits
+const OBJECT_ID_DELTA_BYTES: [usize; 7] = [11, 3, 10, 2, 9, 1, 0];
+
+fn column_error(kind: ErrorKind, offset: usize) -> BsonError {
+    BsonError::new(kind).with_offset(offset)
+}
+
+fn zigzag(value: i128) -> u128 {
+    ((value << 1) ^ (value >> 127)) as u128
+}
+
+fn unzigzag(value: u128) -> i128 {
+    (value >> 1) as i128 ^ -((value & 1) as i128)
+}
+
+fn block_scale(control: u8) -> usize {
+    match control >> 4 {
+        0x8 => SCALE_NONE,
+        nibble => (nibble - 0x9) as usize,
+    }
+}
+
+/// strings, binaries and decimals take 128 bit deltas, other types 64 bit ones
+fn is_wide(element: &Element) -> bool {
+    matches!(
+        element,
+        Element::String(_)
+            | Element::Javascript(_)
+            | Element::Symbol(_)
+            | Element::Binary(_)
+            | Element::Decimal(_)
+    )
+}
+
+/// up to 16 bytes as a big endian integer
+fn bytes_to_int(bytes: &[u8]) -> Option<i128> {
+    (bytes.len() <= 16).then(|| {
+        bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | *byte as i128)
+    })
+}
+
+/// the integer deltas of `element` apply to, `None` for types that can only repeat
+///
+/// Strings lose their leading zero bytes in this form and only take deltas without them.
+fn encode_delta_base(element: &Element, scale: usize) -> Option<i128> {
+    let value = match element {
+        Element::Double(value) if scale == SCALE_NONE => value.to_bits() as i64,
+        Element::Double(value) => {
+            let encoded = (value * DOUBLE_SCALES[scale]).round() as i64;
+            let decoded = encoded as f64 / DOUBLE_SCALES[scale];
+            return (decoded.to_bits() == value.to_bits()).then_some(encoded as i128);
+        }
+        Element::Int32(value) => *value as i64,
+        Element::Int64(value) => *value,
+        Element::Boolean(value) => *value as i64,
+        Element::DateTime(value) => value.millis,
+        Element::Timestamp(value) => u64::from(*value) as i64,
+        Element::ObjectId(id) => {
+            let mut bytes = [0u8; 8];
+            for (byte, index) in bytes.iter_mut().zip(OBJECT_ID_DELTA_BYTES) {
+                *byte = id.id[index];
+            }
+            i64::from_le_bytes(bytes)
+        }
+        Element::String(value) | Element::Javascript(value) | Element::Symbol(value)
+            if !value.starts_with('\0') =>
+        {
+            return bytes_to_int(value.as_bytes())
+        }
+        Element::Binary(binary) => return bytes_to_int(&binary.data),
+        Element::Decimal(value) => return Some(i128::from_le_bytes(value.bytes)),
+        _ => return None,
+    };
+    Some(value as i128)
+}
+
+/// inverse of [`encode_delta_base`], the rest of an ObjectId and the size of a binary come
+/// from `reference`, `None` if `encoded` is not a value of its type
+fn decode_delta_base(reference: &Element, encoded: i128, scale: usize) -> Option<Element> {
+    let value = encoded as i64;
+    let bytes = encoded.to_be_bytes();
+    let string = || {
+        let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(16);
+        String::from_utf8(bytes[start..].to_vec()).ok()
+    };
+    Some(match reference {
+        Element::Double(_) if scale == SCALE_NONE => Element::Double(f64::from_bits(value as u64)),
+        Element::Double(_) => Element::Double(value as f64 / DOUBLE_SCALES[scale]),
+        Element::Int32(_) => Element::Int32(value as i32),
+        Element::Int64(_) => Element::Int64(value),
+        Element::Boolean(_) => Element::Boolean(value != 0),
+        Element::DateTime(_) => Element::DateTime(DateTime::from(value)),
+        Element::Timestamp(_) => Element::Timestamp(Timestamp::from(value as u64)),
+        Element::ObjectId(id) => {
+            let mut id = *id;
+            for (byte, index) in value.to_le_bytes().into_iter().zip(OBJECT_ID_DELTA_BYTES) {
+                id.id[index] = byte;
+            }
+            Element::ObjectId(id)
+        }
+        Element::String(_) => Element::String(string()?),
+        Element::Javascript(_) => Element::Javascript(string()?),
+        Element::Symbol(_) => Element::Symbol(string()?),
+        Element::Binary(binary) => {
+            let (high, data) = bytes.split_at(16 - binary.data.len().min(16));
+            if high.iter().any(|byte| *byte != 0) {
+                return None;
+            }
+            Element::Binary(Binary {
+                binary_type: binary.binary_type,
+                data: data.to_vec(),
+            })
+        }
+        Element::Decimal(_) => Element::Decimal(Decimal128::from(encoded.to_le_bytes())),
+        other => other.clone(),
+    })
+}
+
+/// trailing zero unit of the extended selectors, 0 for the others
+fn trailing_zeros_unit(selector: u64) -> u32 {
+    match selector {
+        7 => 1,
+        8 => 4,
+        _ => 0,
+    }
+}
+
+/// position of the first slot and width of the slots of a layout
+fn slot_layout(selector: u64, bits: u32) -> (usize, u32) {
+    match trailing_zeros_unit(selector) {
+        0 => (4, bits),
+        _ => (8, bits + TRAILING_ZEROS_BITS),
+    }
+}
+
+/// `value` in a slot of a layout, `None` if it does not fit, all ones marks a missing value
+fn pack_slot(value: u128, selector: u64, bits: u32) -> Option<u64> {
+    let limit = (1u128 << bits) - 1;
+    let unit = trailing_zeros_unit(selector);
+    if unit == 0 {
+        return (value < limit).then_some(value as u64);
+    }
+    let zeros = match value {
+        0 => 0,
+        _ => (value.trailing_zeros() / unit).min((1 << TRAILING_ZEROS_BITS) - 1),
+    };
+    let stored = value >> (zeros * unit);
+    (stored < limit).then_some((stored as u64) << TRAILING_ZEROS_BITS | zeros as u64)
+}
+
+/// whether a single slot can store `value`
+fn fits_slot(value: u128) -> bool {
+    LAYOUTS
+        .iter()
+        .filter(|(_, _, _, count)| *count == 1)
+        .any(|(selector, _, bits, _)| pack_slot(value, *selector, *bits).is_some())
+}
+
+/// appends the slots of one Simple-8b block, `None` for missing values
+fn decode_simple8b(
+    block: u64,
+    last: Option<u128>,
+    values: &mut Vec<Option<u128>>,
+    offset: usize,
+) -> Result<(), BsonError> {
+    let selector = block & 0xF;
+    if selector == RLE_SELECTOR {
+        let count = ((block >> 4) & 0xF) as usize + 1;
+        values.extend(std::iter::repeat_n(last, count * RLE_MULTIPLIER));
+        return Ok(());
+    }
+    let extended = match trailing_zeros_unit(selector) {
+        0 => 0,
+        _ => (block >> 4) & 0xF,
+    };
+    let (_, _, bits, count) = LAYOUTS
+        .iter()
+        .find(|layout| (layout.0, layout.1) == (selector, extended))
+        .ok_or_else(|| column_error(ErrorKind::Malformed, offset))?;
+    let unit = trailing_zeros_unit(selector);
+    let (start, width) = slot_layout(selector, *bits);
+    let mask = (1u64 << width) - 1;
+    for index in 0..*count {
+        let slot = (block >> (start + index * width as usize)) & mask;
+        values.push(match slot {
+            slot if slot == mask => None,
+            slot if unit == 0 => Some(slot as u128),
+            slot => {
+                let zeros = (slot & ((1 << TRAILING_ZEROS_BITS) - 1)) as u32;
+                Some(((slot >> TRAILING_ZEROS_BITS) as u128) << (zeros * unit))
+            }
+        });
+    }
+    Ok(())
+}
+
+/// packs slots into Simple-8b blocks, each taking as many values as fit, with their counts
+fn encode_simple8b(mut values: &[Option<u128>]) -> Vec<(u64, usize)> {
+    let mut blocks = vec![];
+    while !values.is_empty() {
+        let (block, count) = LAYOUTS
+            .iter()
+            .filter(|(_, _, _, count)| *count <= values.len())
+            .find_map(|(selector, extended, bits, count)| {
+                let (start, width) = slot_layout(*selector, *bits);
+                let mut block = selector | extended << 4;
+                for (index, value) in values[..*count].iter().enumerate() {
+                    let slot = match value {
+                        Some(value) => pack_slot(*value, *selector, *bits)?,
+                        None => (1 << width) - 1,
+                    };
+                    block |= slot << (start + index * width as usize);
+                }
+                Some((block, *count))
+            })
+            .expect("every slot fits a single value layout");
+        blocks.push((block, count));
+        values = &values[count..];
+    }
+    blocks
+}
+
+/// position in the column being decoded
+struct Reader<'a> {
+    data: &'a [u8],
+    input: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn offset(&self) -> usize {
+        self.data.len() - self.input.len()
+    }
+
+    fn control(&self) -> Result<u8, BsonError> {
+        self.input
+            .first()
+            .copied()
+            .ok_or_else(|| column_error(ErrorKind::Truncated, self.offset()))
+    }
+}
+
+/// decoding state of one sequence of values, the column or a field of interleaved objects
+struct Stream {
+    /// last literal or decoded value, deltas apply to it
+    reference: Option<Element>,
+    /// `reference` in the integer form of `scale`
+    encoded: Option<i128>,
+    scale: usize,
+    /// previous delta of timestamps, which store the delta of the delta
+    delta: i64,
+    /// last slot of the previous block, repeated by run length encoded blocks
+    last_slot: Option<u128>,
+}
+
+impl Stream {
+    fn new(reference: Option<Element>) -> Self {
+        Stream {
+            encoded: reference
+                .as_ref()
+                .and_then(|reference| encode_delta_base(reference, SCALE_NONE)),
+            reference,
+            scale: SCALE_NONE,
+            delta: 0,
+            last_slot: Some(0),
+        }
+    }
+
+    fn read_literal(&mut self, reader: &mut Reader) -> Result<Element, BsonError> {
+        let offset = reader.offset();
+        let (rest, (key, element)) =
+            parse_any_ref(reader.input).map_err(|err| parse_error(reader.data, err, 0))?;
+        if !key.is_empty() {
+            return Err(column_error(ErrorKind::Malformed, offset + 1));
+        }
+        let element = element.to_owned();
+        reader.input = rest;
+        *self = Stream::new(Some(element.clone()));
+        Ok(element)
+    }
+
+    fn read_blocks(
+        &mut self,
+        control: u8,
+        reader: &mut Reader,
+        values: &mut VecDeque<Option<Element>>,
+    ) -> Result<(), BsonError> {
+        let offset = reader.offset();
+        let size = ((control & 0x0F) as usize + 1) * 8;
+        if reader.input.len() < size + 1 {
+            return Err(column_error(ErrorKind::Truncated, reader.data.len()));
+        }
+        let scale = block_scale(control);
+        if scale != self.scale {
+            // doubles change scale between blocks, the reference is stored again in the new one
+            match &self.reference {
+                Some(reference @ Element::Double(_)) => {
+                    self.encoded = Some(
+                        encode_delta_base(reference, scale)
+                            .ok_or_else(|| column_error(ErrorKind::Malformed, offset))?,
+                    );
+                    self.scale = scale;
+                }
+                _ => return Err(column_error(ErrorKind::Malformed, offset)),
+            }
+        }
+
+        let mut slots = vec![];
+        for (index, block) in reader.input[1..size + 1].chunks_exact(8).enumerate() {
+            let block = u64::from_le_bytes(block.try_into().expect("blocks are 8 bytes"));
+            let block_offset = offset + 1 + index * 8;
+            decode_simple8b(block, self.last_slot, &mut slots, block_offset)?;
+            self.last_slot = slots.last().copied().unwrap_or(self.last_slot);
+        }
+        for slot in slots {
+            let value = match slot {
+                Some(slot) => Some(self.apply_delta(slot, offset)?),
+                None => None,
+            };
+            values.push_back(value);
+        }
+        reader.input = &reader.input[size + 1..];
+        Ok(())
+    }
+
+    fn apply_delta(&mut self, slot: u128, offset: usize) -> Result<Element, BsonError> {
+        let malformed = || column_error(ErrorKind::Malformed, offset);
+        let reference = self.reference.as_ref().ok_or_else(malformed)?;
+        let value = match self.encoded {
+            Some(encoded) if is_wide(reference) => {
+                let encoded = encoded.wrapping_add(unzigzag(slot));
+                self.encoded = Some(encoded);
+                decode_delta_base(reference, encoded, self.scale).ok_or_else(malformed)?
+            }
+            Some(encoded) => {
+                let delta = u64::try_from(slot).map_err(|_| malformed())?;
+                let delta = unzigzag(delta as u128) as i64;
+                let delta = if let Element::Timestamp(_) = reference {
+                    self.delta = self.delta.wrapping_add(delta);
+                    self.delta
+                } else {
+                    delta
+                };
+                let encoded = (encoded as i64).wrapping_add(delta) as i128;
+                self.encoded = Some(encoded);
+                decode_delta_base(reference, encoded, self.scale).ok_or_else(malformed)?
+            }
+            None if slot == 0 => reference.clone(),
+            None => return Err(malformed()),
+        };
+        self.reference = Some(value.clone());
+        Ok(value)
+    }
+}
+
+/// fields of the reference object of interleaved mode, scalars index their stream
+enum Shape {
+    Scalar(usize),
+    Object {
+        array: bool,
+        fields: Vec<(String, Shape)>,
+    },
+}
+
+impl Shape {
+    /// shape of the object with body `data`, adding a stream for each scalar starting at its value
+    fn new(data: &[u8], array: bool, streams: &mut Vec<Stream>) -> Result<Shape, BsonError> {
+        let mut fields = vec![];
+        let mut input = data;
+        while !input.is_empty() {
+            let (rest, (key, element)) = decode_element(data, input)?;
+            let shape = match element.to_owned() {
+                Element::EmbededDocument(doc) => Shape::new(&doc.data, false, streams),
+                Element::ArrayDocument(array) => Shape::new(&array.data, true, streams),
+                scalar => {
+                    streams.push(Stream::new(Some(scalar)));
+                    Ok(Shape::Scalar(streams.len() - 1))
+                }
+            };
+            fields.push((
+                key.to_string(),
+                shape.map_err(|error| error.nested(key, 0))?,
+            ));
+            input = rest;
+        }
+        Ok(Shape::Object { array, fields })
+    }
+
+    fn has_scalars(&self) -> bool {
+        match self {
+            Shape::Scalar(_) => true,
+            Shape::Object { fields, .. } => fields.iter().any(|(_, shape)| shape.has_scalars()),
+        }
+    }
+
+    /// the object of one row from the values of the streams, `None` when all of them are missing
+    fn build(&self, values: &[Option<Element>]) -> Option<Element> {
+        let (array, fields) = match self {
+            Shape::Scalar(index) => return values[*index].clone(),
+            Shape::Object { array, fields } => (array, fields),
+        };
+        let present: Vec<(&str, Element)> = fields
+            .iter()
+            .filter_map(|(key, shape)| Some((key.as_str(), shape.build(values)?)))
+            .collect();
+        if present.is_empty() && self.has_scalars() {
+            return None;
+        }
+        Some(if *array {
+            let mut builder = ArrayBuilder::new();
+            for (_, element) in &present {
+                builder.append(element);
+            }
+            builder.build().into()
+        } else {
+            let mut builder = DocumentBuilder::new();
+            for (key, element) in &present {
+                builder.append(key, element);
+            }
+            builder.build().into()
+        })
+    }
+}
+
+/// Values of a BSON Column (binary subtype 7), `None` for missing values
+///
+/// Literal elements, skips and Simple-8b blocks of deltas, including the extended selectors and
+/// run length encoded blocks, 128 bit deltas of strings, binaries and decimals, delta of delta
+/// for timestamps and interleaved objects and arrays are supported. Malformed input ends the
+/// iteration with an error.
+pub struct ColumnIter<'a> {
+    reader: Reader<'a>,
+    stream: Stream,
+    pending: VecDeque<Option<Element>>,
+    done: bool,
+}
+
+impl<'a> ColumnIter<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        ColumnIter {
+            reader: Reader { data, input: data },
+            stream: Stream::new(None),
+            pending: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    fn read_control(&mut self) -> Result<(), BsonError> {
+        let offset = self.reader.offset();
+        let control = self.reader.control()?;
+        match control {
+            0x00 => {
+                self.done = true;
+                if self.reader.input.len() > 1 {
+                    return Err(column_error(ErrorKind::Malformed, offset + 1));
+                }
+                self.reader.input = &self.reader.input[1..];
+            }
+            0x01..=0x1F | 0x7F | 0xFF => {
+                let element = self.stream.read_literal(&mut self.reader)?;
+                self.pending.push_back(Some(element));
+            }
+            0x80..=0xDF => self
+                .stream
+                .read_blocks(control, &mut self.reader, &mut self.pending)?,
+            INTERLEAVED_LEGACY..=INTERLEAVED_ARRAY => self.read_interleaved(control)?,
+            _ => return Err(column_error(ErrorKind::Malformed, offset)),
+        }
+        Ok(())
+    }
+
+    /// Rows of interleaved mode up to its end byte
+    ///
+    /// Every scalar of the reference object has its own stream of literals and blocks, each
+    /// starting at the scalar's value. A stream reads its next control byte when it used up the
+    /// values of the previous one, so those of all streams follow each other in the order rows
+    /// need them.
+    fn read_interleaved(&mut self, control: u8) -> Result<(), BsonError> {
+        let reader = &mut self.reader;
+        let offset = reader.offset();
+        let (rest, reference) =
+            parse_document(&reader.input[1..]).map_err(|err| parse_error(reader.data, err, 0))?;
+        // the parser leaves the null byte ending the reference object
+        let rest = match rest {
+            [0, rest @ ..] => rest,
+            _ => {
+                return Err(column_error(
+                    ErrorKind::Malformed,
+                    reader.data.len() - rest.len(),
+                ))
+            }
+        };
+        let mut streams = vec![];
+        let shape = Shape::new(&reference.data, control == INTERLEAVED_ARRAY, &mut streams)?;
+        if streams.is_empty() {
+            return Err(column_error(ErrorKind::Malformed, offset));
+        }
+        reader.input = rest;
+
+        let mut queues = vec![VecDeque::new(); streams.len()];
+        loop {
+            if queues.iter().all(VecDeque::is_empty) && reader.control()? == 0x00 {
+                reader.input = &reader.input[1..];
+                break;
+            }
+            for (stream, queue) in streams.iter_mut().zip(&mut queues) {
+                if !queue.is_empty() {
+                    continue;
+                }
+                match reader.control()? {
+                    0x01..=0x1F | 0x7F | 0xFF => {
+                        queue.push_back(Some(stream.read_literal(reader)?));
+                    }
+                    control @ 0x80..=0xDF => stream.read_blocks(control, reader, queue)?,
+                    _ => return Err(column_error(ErrorKind::Malformed, reader.offset())),
+                }
+            }
+            let values: Vec<Option<Element>> = queues
+                .iter_mut()
+                .map(|queue| queue.pop_front().flatten())
+                .collect();
+            self.pending.push_back(shape.build(&values));
+        }
+        self.stream = Stream::new(None);
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for ColumnIter<'a> {
+    type Item = Result<Option<Element>, BsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.pending.pop_front() {
+                return Some(Ok(value));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(error) = self.read_control() {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+    }
+}
+
+/// encoder of one sequence of values, writing chunks of literals and blocks with the number of
+/// values each holds
+#[derive(Default)]
+struct StreamEncoder {
+    chunks: Vec<(Vec<u8>, usize)>,
+    reference: Option<Element>,
+    encoded: Option<i128>,
+    delta: i64,
+    slots: Vec<Option<u128>>,
+}
+
+impl StreamEncoder {
+    fn new(reference: Element) -> Self {
+        StreamEncoder {
+            encoded: encode_delta_base(&reference, SCALE_NONE),
+            reference: Some(reference),
+            ..Default::default()
+        }
+    }
+
+    fn append(&mut self, value: Option<Element>) {
+        let Some(element) = value else {
+            self.slots.push(None);
+            return;
+        };
+        if let Some(slot) = self.delta_slot(&element) {
+            self.slots.push(Some(slot));
+        } else {
+            self.flush();
+            let mut buf = vec![];
+            encode_element(&mut buf, "", &element);
+            self.chunks.push((buf, 1));
+            self.encoded = encode_delta_base(&element, SCALE_NONE);
+            self.delta = 0;
+        }
+        self.reference = Some(element);
+    }
+
+    /// the slot storing `element` as a delta from the previous value, if it can be one
+    fn delta_slot(&mut self, element: &Element) -> Option<u128> {
+        let reference = self.reference.as_ref()?;
+        if reference.element_type() != element.element_type() {
+            return None;
+        }
+        match (reference, element) {
+            (Element::ObjectId(previous), Element::ObjectId(id))
+                if previous.id[4..9] != id.id[4..9] =>
+            {
+                return None
+            }
+            (Element::Binary(previous), Element::Binary(binary))
+                if previous.binary_type != binary.binary_type
+                    || previous.data.len() != binary.data.len() =>
+            {
+                return None
+            }
+            _ => {}
+        }
+        match (self.encoded, encode_delta_base(element, SCALE_NONE)) {
+            (Some(previous), Some(encoded)) => {
+                let (slot, delta) = if is_wide(element) {
+                    (zigzag(encoded.wrapping_sub(previous)), 0)
+                } else {
+                    let delta = (encoded as i64).wrapping_sub(previous as i64);
+                    match element {
+                        Element::Timestamp(_) => {
+                            (zigzag(delta.wrapping_sub(self.delta) as i128), delta)
+                        }
+                        _ => (zigzag(delta as i128), delta),
+                    }
+                };
+                if !fits_slot(slot) {
+                    return None;
+                }
+                self.encoded = Some(encoded);
+                self.delta = delta;
+                Some(slot)
+            }
+            // other types repeat the previous value, compared by their encoding
+            (None, None) => {
+                let (mut previous, mut next) = (vec![], vec![]);
+                reference.encode_into(&mut previous);
+                element.encode_into(&mut next);
+                (previous == next).then_some(0)
+            }
+            _ => None,
+        }
+    }
+
+    fn flush(&mut self) {
+        let blocks = encode_simple8b(&self.slots);
+        for chunk in blocks.chunks(MAX_BLOCKS) {
+            let mut buf = vec![0x80 | (chunk.len() - 1) as u8];
+            for (block, _) in chunk {
+                buf.extend_from_slice(&block.to_le_bytes());
+            }
+            self.chunks
+                .push((buf, chunk.iter().map(|(_, count)| count).sum()));
+        }
+        self.slots.clear();
+    }
+
+    fn finish(mut self) -> Vec<(Vec<u8>, usize)> {
+        self.flush();
+        self.chunks
+    }
+}
+
+/// depth, key and, for objects, whether it is an array of a field
+type Field = (usize, String, Option<bool>);
+
+/// fields of an object in preorder and its scalars
+fn flatten(element: &Element, depth: usize, shape: &mut Vec<Field>, scalars: &mut Vec<Element>) {
+    let fields: Vec<(String, Element)> = match element {
+        Element::EmbededDocument(doc) => doc.iter().collect(),
+        Element::ArrayDocument(array) => Document {
+            data: array.data.clone(),
+        }
+        .iter()
+        .collect(),
+        _ => return,
+    };
+    for (key, value) in fields {
+        match &value {
+            Element::EmbededDocument(_) | Element::ArrayDocument(_) => {
+                let array = matches!(value, Element::ArrayDocument(_));
+                shape.push((depth, key, Some(array)));
+                flatten(&value, depth + 1, shape, scalars);
+            }
+            _ => {
+                shape.push((depth, key, None));
+                scalars.push(value);
+            }
+        }
+    }
+}
+
+/// writes the column, runs of objects with the same fields go to interleaved mode
+#[derive(Default)]
+struct ColumnEncoder {
+    buf: Vec<u8>,
+    scalars: StreamEncoder,
+    /// objects of the current interleaved run and the skips following them
+    run: Vec<Option<Element>>,
+    run_shape: Vec<Field>,
+}
+
+impl ColumnEncoder {
+    fn append(&mut self, value: Option<Element>) {
+        let (mut shape, mut scalars) = (vec![], vec![]);
+        if let Some(object) = &value {
+            flatten(object, 0, &mut shape, &mut scalars);
+        }
+        match value {
+            Some(object) if !scalars.is_empty() => {
+                if self.run.is_empty() || self.run_shape != shape {
+                    self.flush_run();
+                    for (bytes, _) in std::mem::take(&mut self.scalars).finish() {
+                        self.buf.extend(bytes);
+                    }
+                    self.run_shape = shape;
+                }
+                self.run.push(Some(object));
+            }
+            None if !self.run.is_empty() => self.run.push(None),
+            value => {
+                self.flush_run();
+                self.scalars.append(value);
+            }
+        }
+    }
+
+    fn flush_run(&mut self) {
+        let Some(Some(reference)) = self.run.first() else {
+            return;
+        };
+        let (mut streams, mut chunks) = (vec![], vec![]);
+        let (bytes, control) = match reference {
+            Element::ArrayDocument(array) => (array.to_bytes(), INTERLEAVED_ARRAY),
+            Element::EmbededDocument(doc) => (doc.to_bytes(), INTERLEAVED),
+            _ => unreachable!("runs hold objects"),
+        };
+        self.buf.push(control);
+        self.buf.extend(bytes);
+        for value in &self.run {
+            let mut scalars = vec![];
+            if let Some(object) = value {
+                flatten(object, 0, &mut vec![], &mut scalars);
+            }
+            if streams.is_empty() {
+                streams = scalars.iter().cloned().map(StreamEncoder::new).collect();
+            }
+            for (index, stream) in streams.iter_mut().enumerate() {
+                stream.append(scalars.get(index).cloned());
+            }
+        }
+        for stream in streams {
+            chunks.push(VecDeque::from(stream.finish()));
+        }
+        // chunks go out in the order the decoder reads them, see `ColumnIter::read_interleaved`
+        let mut remaining = vec![0; chunks.len()];
+        for _ in &self.run {
+            for (left, chunks) in remaining.iter_mut().zip(&mut chunks) {
+                if *left == 0 {
+                    let (bytes, count) = chunks.pop_front().expect("a value for every row");
+                    self.buf.extend(bytes);
+                    *left = count;
+                }
+                *left -= 1;
+            }
+        }
+        self.buf.push(0);
+        self.run.clear();
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.flush_run();
+        for (bytes, _) in self.scalars.finish() {
+            self.buf.extend(bytes);
+        }
+        self.buf.push(0);
+        self.buf
+    }
+}
+
+impl Binary {
+    /// Compresses `values` into a BSON Column (subtype 7), `None` marks a missing value
+    ///
+    /// Values of the previous value's type are stored as Simple-8b deltas where the type allows
+    /// it, anything else as a literal element. Runs of objects or arrays with the same fields are
+    /// written in interleaved mode.
+    ///
+    /// ```rust
+    /// use bson2::{Binary, Element};
+    ///
+    /// let values = [Some(Element::Int64(10)), None, Some(Element::Int64(12))];
+    /// let column = Binary::from_column(values.clone());
+    /// let decoded: Result<Vec<_>, _> = column.column_iter().unwrap().collect();
+    /// assert_eq!(Ok(values.to_vec()), decoded);
+    /// ```
+    pub fn from_column(values: impl IntoIterator<Item = Option<Element>>) -> Binary {
+        let mut encoder = ColumnEncoder::default();
+        for value in values {
+            encoder.append(value);
+        }
+        Binary {
+            binary_type: BinaryType::BinaryCompressed,
+            data: encoder.finish(),
+        }
+    }
+
+    /// iterates the values of a BSON Column, the binary must be of subtype 7
+    pub fn column_iter(&self) -> Result<ColumnIter<'_>, BsonError> {
+        if self.binary_type != BinaryType::BinaryCompressed {
+            return Err(BsonError::custom_message(format!(
+                "binary subtype 0x{:02x} is not a bson column",
+                u8::from(&self.binary_type)
+            )));
+        }
+        Ok(ColumnIter::new(&self.data))
+    }
+}
@@ -0,0 +1,261 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::element::*;
+use super::error::*;
+
+/// BSON datetime, milliseconds since the unix epoch
+///
+/// Every i64 is a valid value, negative ones are before 1970. Only the years 0000 to 9999 have
+/// an RFC 3339 form, [`DateTime::to_rfc3339`] fails outside of them.
+///
+/// ```rust
+/// use bson2::DateTime;
+///
+/// let date = DateTime::parse_rfc3339("1969-07-20T20:17:40.5+00:00").unwrap();
+/// assert_eq!(-14_182_939_500, date.millis);
+/// assert_eq!("1969-07-20T20:17:40.500Z", date.to_rfc3339().unwrap());
+/// assert!(DateTime::MAX.to_rfc3339().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    pub millis: i64,
+}
+
+impl From<i64> for DateTime {
+    fn from(millis: i64) -> Self {
+        Self { millis }
+    }
+}
+
+impl From<DateTime> for Element {
+    fn from(value: DateTime) -> Self {
+        Element::DateTime(value)
+    }
+}
+
+impl From<SystemTime> for DateTime {
+    /// rounds toward the past to whole milliseconds, saturates at [`DateTime::MIN`] and
+    /// [`DateTime::MAX`]
+    fn from(time: SystemTime) -> Self {
+        let millis = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_millis() as i128,
+            Err(before) => {
+                let before = before.duration();
+                let partial = before.subsec_nanos() % 1_000_000 != 0;
+                -(before.as_millis() as i128) - partial as i128
+            }
+        };
+        DateTime::from(millis.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl TryFrom<DateTime> for SystemTime {
+    type Error = BsonError;
+
+    /// fails when the platform's `SystemTime` can not reach the date
+    fn try_from(value: DateTime) -> Result<Self, Self::Error> {
+        let offset = Duration::from_millis(value.millis.unsigned_abs());
+        let time = if value.millis >= 0 {
+            UNIX_EPOCH.checked_add(offset)
+        } else {
+            UNIX_EPOCH.checked_sub(offset)
+        };
+        time.ok_or_else(|| {
+            BsonError::custom_message(format!(
+                "datetime {} is out of range for SystemTime",
+                value.millis
+            ))
+        })
+    }
+}
+
+/// 0000-01-01T00:00:00Z and 10000-01-01T00:00:00Z, the range RFC 3339 can express
+const MIN_RFC3339_MILLIS: i64 = -62_167_219_200_000;
+const END_RFC3339_MILLIS: i64 = 253_402_300_800_000;
+
+impl DateTime {
+    pub const MIN: DateTime = DateTime { millis: i64::MIN };
+    pub const MAX: DateTime = DateTime { millis: i64::MAX };
+
+    pub fn now() -> DateTime {
+        SystemTime::now().into()
+    }
+
+    /// `YYYY-MM-DDTHH:MM:SS.mmmZ` in UTC, fails for dates before year 0 or after year 9999
+    pub fn to_rfc3339(&self) -> Result<String, BsonError> {
+        if !(MIN_RFC3339_MILLIS..END_RFC3339_MILLIS).contains(&self.millis) {
+            return Err(BsonError::custom_message(format!(
+                "datetime {} has no rfc 3339 form",
+                self.millis
+            )));
+        }
+        let mut out = self.to_iso8601();
+        if self.millis.rem_euclid(1000) == 0 {
+            out.insert_str(out.len() - 1, ".000");
+        }
+        Ok(out)
+    }
+
+    /// parses an RFC 3339 date time, offsets are applied and fractions beyond milliseconds
+    /// truncated
+    pub fn parse_rfc3339(s: &str) -> Result<DateTime, BsonError> {
+        DateTime::from_iso8601(s)
+            .ok_or_else(|| BsonError::custom_message(format!("invalid rfc 3339 date {s:?}")))
+    }
+}
+
+impl std::fmt::Display for DateTime {
+    /// the RFC 3339 form, or the raw milliseconds for dates it can not express
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.to_rfc3339() {
+            Ok(text) => f.write_str(&text),
+            Err(_) => write!(f, "DateTime({})", self.millis),
+        }
+    }
+}
+
+/// days since 1970-01-01 to (year, month, day), proleptic gregorian calendar
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+impl DateTime {
+    /// `YYYY-MM-DDTHH:MM:SS[.mmm]Z`, milliseconds are omitted when zero
+    pub(crate) fn to_iso8601(self) -> String {
+        let days = self.millis.div_euclid(86_400_000);
+        let millis_of_day = self.millis.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days);
+        let seconds = millis_of_day / 1000;
+        let millis = millis_of_day % 1000;
+        let mut out = format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        if millis != 0 {
+            out.push_str(&format!(".{millis:03}"));
+        }
+        out.push('Z');
+        out
+    }
+}
+
+/// (year, month, day) to days since 1970-01-01, inverse of [`civil_from_days`]
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+impl DateTime {
+    /// parses `YYYY-MM-DDTHH:MM:SS[.fff](Z|+HH:MM|-HH:MM)`, fractions beyond milliseconds
+    /// are truncated
+    pub(crate) fn from_iso8601(s: &str) -> Option<DateTime> {
+        if !s.is_ascii() || s.len() < 20 {
+            return None;
+        }
+        let (date, time) = s.split_once(['T', 't'])?;
+        let mut date_parts = date.split('-');
+        let year = date_parts
+            .next()
+            .filter(|y| y.len() == 4)
+            .and_then(parse_number)? as i64;
+        let month = date_parts
+            .next()
+            .filter(|m| m.len() == 2)
+            .and_then(parse_number)?;
+        let day = date_parts
+            .next()
+            .filter(|d| d.len() == 2)
+            .and_then(parse_number)?;
+        if date_parts.next().is_some()
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+        {
+            return None;
+        }
+
+        let offset_start = time.find(['Z', 'z', '+', '-'])?;
+        let (clock, offset) = time.split_at(offset_start);
+        let (clock, fraction) = match clock.split_once('.') {
+            Some((clock, fraction)) => (clock, Some(fraction)),
+            None => (clock, None),
+        };
+        let mut clock_parts = clock.split(':');
+        let mut next = || {
+            clock_parts
+                .next()
+                .filter(|p| p.len() == 2)
+                .and_then(parse_number)
+        };
+        let (hour, minute, second) = (next()?, next()?, next()?);
+        if clock.len() != 8 || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        let millis = match fraction {
+            Some(fraction) => {
+                // any number of digits is allowed, only milliseconds are kept
+                if fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let truncated = &fraction[..fraction.len().min(3)];
+                parse_number(&format!("{truncated:0<3}"))? as i64
+            }
+            None => 0,
+        };
+
+        let offset_minutes = match offset {
+            "Z" | "z" => 0,
+            _ => {
+                // time-numoffset of RFC 3339, exactly a sign, two digits, a colon and two digits
+                let sign = if offset.starts_with('-') { -1 } else { 1 };
+                let (hours, minutes) = offset[1..].split_once(':')?;
+                if hours.len() != 2 || minutes.len() != 2 {
+                    return None;
+                }
+                let hours = parse_number(hours)? as i64;
+                let minutes = parse_number(minutes)? as i64;
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                sign * (hours * 60 + minutes)
+            }
+        };
+
+        let days = days_from_civil(year, month, day);
+        let seconds =
+            days * 86_400 + (hour * 3600 + minute * 60 + second) as i64 - offset_minutes * 60;
+        Some(DateTime::from(seconds * 1000 + millis))
+    }
+}
@@ -0,0 +1,449 @@
+use serde::de::{
+    self, value::MapDeserializer, value::SeqDeserializer, Deserialize, DeserializeOwned,
+    DeserializeSeed, IntoDeserializer, Visitor,
+};
+
+use super::builder::*;
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+use super::ser::*;
+use super::timestamp::*;
+
+/// Deserializes `T` from a [`Document`]
+///
+/// ```rust
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct User {
+///     name: String,
+///     age: i32,
+/// }
+///
+/// let doc = bson2::doc! { "name": "hi", "age": 5 };
+/// let user: User = bson2::from_document(&doc).unwrap();
+/// assert_eq!(User { name: "hi".to_string(), age: 5 }, user);
+/// ```
+pub fn from_document<T: DeserializeOwned>(doc: &Document) -> Result<T, BsonError> {
+    T::deserialize(Deserializer::new(Element::EmbededDocument(doc.clone())))
+}
+
+/// Deserializes `T` from a single [`Element`]
+pub fn from_element<T: DeserializeOwned>(element: Element) -> Result<T, BsonError> {
+    T::deserialize(Deserializer::new(element))
+}
+
+/// Deserializes `T` straight from the bytes of a bson document
+pub fn from_slice<T: DeserializeOwned>(input: &[u8]) -> Result<T, BsonError> {
+    let doc = Document::try_from(input)?;
+    T::deserialize(Deserializer::new(Element::EmbededDocument(doc)))
+}
+
+impl de::Error for BsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        BsonError::custom_message(msg.to_string())
+    }
+}
+
+/// Serde deserializer reading from an [`Element`], nested documents and arrays are decoded
+/// lazily from their raw bytes
+pub struct Deserializer {
+    element: Element,
+}
+
+impl Deserializer {
+    pub fn new(element: Element) -> Self {
+        Self { element }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, BsonError> for Element {
+    type Deserializer = Deserializer;
+
+    fn into_deserializer(self) -> Deserializer {
+        Deserializer::new(self)
+    }
+}
+
+/// payload the private newtypes of [`ser`](crate::ser) are (de)serialized through
+fn special_payload(name: &str, element: Element) -> Result<Element, Element> {
+    match (name, element) {
+        (OBJECT_ID_NEWTYPE, Element::ObjectId(id)) => Ok(Element::String(id.to_string())),
+        (BINARY_NEWTYPE, Element::Binary(binary)) => {
+            let mut builder = ArrayBuilder::new();
+            builder
+                .append_int32(u8::from(&binary.binary_type).into())
+                .append_binary(&Binary {
+                    binary_type: BinaryType::BinaryGeneric,
+                    data: binary.data,
+                });
+            Ok(Element::ArrayDocument(builder.build()))
+        }
+        (DECIMAL128_NEWTYPE, Element::Decimal(value)) => Ok(Element::Binary(Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: value.bytes.to_vec(),
+        })),
+        (DATETIME_NEWTYPE, Element::DateTime(date)) => Ok(Element::Int64(date.millis)),
+        (TIMESTAMP_NEWTYPE, Element::Timestamp(value)) => {
+            Ok(Element::Int64(u64::from(value) as i64))
+        }
+        (_, element) => Err(element),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = BsonError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BsonError> {
+        match self.element {
+            Element::Double(v) => visitor.visit_f64(v),
+            Element::String(v) | Element::Javascript(v) | Element::Symbol(v) => {
+                visitor.visit_string(v)
+            }
+            Element::EmbededDocument(doc) => visitor.visit_map(ElementsAccess::new(doc.data)),
+            Element::ArrayDocument(array) => visitor.visit_seq(ElementsAccess::new(array.data)),
+            Element::Binary(binary) => visitor.visit_byte_buf(binary.data),
+            Element::RegularExpression { pattern, options } => {
+                visitor.visit_map(MapDeserializer::new(
+                    [
+                        ("pattern", Element::String(pattern)),
+                        ("options", Element::String(options)),
+                    ]
+                    .into_iter(),
+                ))
+            }
+            Element::Undefined | Element::Null | Element::Min | Element::Max => {
+                visitor.visit_unit()
+            }
+            Element::ObjectId(id) => visitor.visit_string(id.to_string()),
+            Element::Boolean(v) => visitor.visit_bool(v),
+            Element::DateTime(v) => visitor.visit_i64(v.millis),
+            Element::Int64(v) => visitor.visit_i64(v),
+            Element::DbPointer(pointer) => visitor.visit_map(MapDeserializer::new(
+                [
+                    ("namespace", Element::String(pointer.namespace)),
+                    ("id", Element::ObjectId(pointer.id)),
+                ]
+                .into_iter(),
+            )),
+            Element::JavascriptCode(value) => visitor.visit_map(MapDeserializer::new(
+                [
+                    ("code", Element::String(value.code)),
+                    ("scope", Element::EmbededDocument(value.scope)),
+                ]
+                .into_iter(),
+            )),
+            Element::Int32(v) => visitor.visit_i32(v),
+            Element::Timestamp(v) => visitor.visit_u64(v.into()),
+            Element::Decimal(v) => visitor.visit_bytes(&v.bytes),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BsonError> {
+        match self.element {
+            Element::Null | Element::Undefined => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, BsonError> {
+        match special_payload(name, self.element) {
+            Ok(payload) => visitor.visit_newtype_struct(Deserializer::new(payload)),
+            Err(element) => visitor.visit_newtype_struct(Deserializer::new(element)),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BsonError> {
+        match self.element {
+            Element::Binary(binary) => {
+                let mut seq = SeqDeserializer::<_, BsonError>::new(binary.data.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            element => Deserializer::new(element).deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, BsonError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BsonError> {
+        match self.element {
+            Element::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Element::EmbededDocument(doc) => {
+                let mut iter = doc.iter();
+                match (iter.next(), iter.next()) {
+                    (Some((variant, value)), None) => {
+                        visitor.visit_enum(EnumDeserializer { variant, value })
+                    }
+                    _ => Err(BsonError::custom_message(
+                        "enum document must have exactly one key".to_string(),
+                    )),
+                }
+            }
+            _ => Err(BsonError::custom_message(
+                "enum must be a string or a document".to_string(),
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// walks the raw elements of a document or array, serving keys and values lazily
+struct ElementsAccess {
+    data: Vec<u8>,
+    offset: usize,
+    value: Option<Element>,
+    /// key and offset of the value being deserialized, used to locate nested errors
+    current: Option<(String, usize)>,
+}
+
+impl ElementsAccess {
+    fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            offset: 0,
+            value: None,
+            current: None,
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<KeyPair<Element>>, BsonError> {
+        let input = &self.data[self.offset..];
+        if input.is_empty() {
+            return Ok(None);
+        }
+        let (rest, (key, element)) = decode_element(&self.data, input)?;
+        // type byte, key and its NUL, plus the length prefix of the parent document
+        let value_offset = self.offset + key.len() + 2 + 4;
+        self.current = Some((key.to_string(), value_offset));
+        self.offset = self.data.len() - rest.len();
+        Ok(Some((key.to_string(), element.to_owned())))
+    }
+
+    fn locate(&self, error: BsonError) -> BsonError {
+        match &self.current {
+            Some((key, value_offset)) => error.nested(key, *value_offset),
+            None => error,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ElementsAccess {
+    type Error = BsonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, BsonError> {
+        match self.next_element()? {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, BsonError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| BsonError::custom_message("value requested before key".to_string()))?;
+        seed.deserialize(Deserializer::new(value))
+            .map_err(|error| self.locate(error))
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for ElementsAccess {
+    type Error = BsonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, BsonError> {
+        match self.next_element()? {
+            Some((_, value)) => seed
+                .deserialize(Deserializer::new(value))
+                .map(Some)
+                .map_err(|error| self.locate(error)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// enum stored as `{ variant: value }`
+struct EnumDeserializer {
+    variant: String,
+    value: Element,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = BsonError;
+    type Variant = Deserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Deserializer), BsonError> {
+        let variant: de::value::StringDeserializer<BsonError> = self.variant.into_deserializer();
+        let variant = seed.deserialize(variant)?;
+        Ok((variant, Deserializer::new(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+    type Error = BsonError;
+
+    fn unit_variant(self) -> Result<(), BsonError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, BsonError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, BsonError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BsonError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+/// owned bytes accepting bson binary as well as sequences of integers
+struct ByteBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("bytes")
+            }
+
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<ByteBuf, E> {
+                Ok(ByteBuf(v))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<ByteBuf, A::Error> {
+                let mut bytes = vec![];
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+/// visitor for the private newtypes, `T` is the payload type
+struct NewtypeVisitor<T>(&'static str, std::marker::PhantomData<T>);
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for NewtypeVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "bson {}", self.0)
+    }
+
+    fn visit_newtype_struct<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        T::deserialize(deserializer)
+    }
+}
+
+fn deserialize_newtype<'de, D: de::Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+    name: &'static str,
+    expecting: &'static str,
+) -> Result<T, D::Error> {
+    deserializer.deserialize_newtype_struct(name, NewtypeVisitor(expecting, Default::default()))
+}
+
+impl<'de> Deserialize<'de> for ObjectId {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex: String = deserialize_newtype(deserializer, OBJECT_ID_NEWTYPE, "ObjectId")?;
+        let mut id = [0u8; 12];
+        hex::decode_to_slice(&hex, &mut id)
+            .map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&hex), &"24 hex digits"))?;
+        Ok(id.into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Binary {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (subtype, ByteBuf(data)): (u8, ByteBuf) =
+            deserialize_newtype(deserializer, BINARY_NEWTYPE, "Binary")?;
+        Ok(Binary {
+            binary_type: BinaryType::from(subtype),
+            data,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal128 {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ByteBuf(data) = deserialize_newtype(deserializer, DECIMAL128_NEWTYPE, "Decimal128")?;
+        let bytes: [u8; 16] = data
+            .try_into()
+            .map_err(|data: Vec<u8>| de::Error::invalid_length(data.len(), &"16 bytes"))?;
+        Ok(bytes.into())
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTime {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let millis: i64 = deserialize_newtype(deserializer, DATETIME_NEWTYPE, "DateTime")?;
+        Ok(millis.into())
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value: i64 = deserialize_newtype(deserializer, TIMESTAMP_NEWTYPE, "Timestamp")?;
+        Ok((value as u64).into())
+    }
+}
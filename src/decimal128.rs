@@ -0,0 +1,365 @@
+use std::cmp::Ordering;
+
+use super::element::*;
+use super::error::*;
+
+/// IEEE 754-2008 128-bit decimal in BID encoding, little endian as stored in BSON
+///
+/// Equality and ordering are numeric like for `f64`: `1.0 == 1.00`, `-0 == 0` and NaN is
+/// unordered, compare [`Decimal128::bytes`] for the exact encoding.
+///
+/// ```rust
+/// use bson2::Decimal128;
+///
+/// let price: Decimal128 = "19.90".parse().unwrap();
+/// assert_eq!("19.90", price.to_string());
+/// assert!(price == "19.9".parse().unwrap());
+/// assert!(price < Decimal128::from(20));
+/// assert_eq!(Ok(20), i64::try_from("2.0E+1".parse::<Decimal128>().unwrap()));
+/// assert_eq!(19.9, price.to_f64());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal128 {
+    pub bytes: [u8; 16],
+}
+
+impl From<[u8; 16]> for Decimal128 {
+    fn from(bytes: [u8; 16]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl From<Decimal128> for Element {
+    fn from(value: Decimal128) -> Self {
+        Element::Decimal(value)
+    }
+}
+
+pub(crate) const EXPONENT_BIAS: i32 = 6176;
+pub(crate) const MAX_SIGNIFICAND: u128 = 9_999_999_999_999_999_999_999_999_999_999_999;
+
+impl Decimal128 {
+    fn high(&self) -> u64 {
+        u64::from_le_bytes(self.bytes[8..].try_into().unwrap())
+    }
+
+    fn low(&self) -> u64 {
+        u64::from_le_bytes(self.bytes[..8].try_into().unwrap())
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.high() >> 63 == 1
+    }
+
+    pub fn is_nan(&self) -> bool {
+        (self.high() >> 58) & 0x1F == 0x1F
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        (self.high() >> 58) & 0x1F == 0x1E
+    }
+
+    /// unbiased exponent and coefficient of a finite value, non canonical coefficients are 0
+    pub(crate) fn parts(&self) -> (i32, u128) {
+        let high = self.high();
+        let (exponent, significand) = if (high >> 61) & 0b11 == 0b11 {
+            // the implied 0b100 prefix always exceeds 34 digits
+            ((high >> 47) & 0x3FFF, 0)
+        } else {
+            let significand = ((high as u128 & 0x1_FFFF_FFFF_FFFF) << 64) | self.low() as u128;
+            ((high >> 49) & 0x3FFF, significand)
+        };
+        let significand = if significand > MAX_SIGNIFICAND {
+            0
+        } else {
+            significand
+        };
+        (exponent as i32 - EXPONENT_BIAS, significand)
+    }
+}
+
+impl std::fmt::Display for Decimal128 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.is_nan() {
+            return f.write_str("NaN");
+        }
+        if self.is_negative() {
+            f.write_str("-")?;
+        }
+        if self.is_infinite() {
+            return f.write_str("Infinity");
+        }
+        let (exponent, significand) = self.parts();
+        let digits = significand.to_string();
+        let adjusted = exponent + digits.len() as i32 - 1;
+        if exponent <= 0 && adjusted >= -6 {
+            if exponent == 0 {
+                return f.write_str(&digits);
+            }
+            let point = digits.len() as i32 + exponent;
+            if point > 0 {
+                let (int, frac) = digits.split_at(point as usize);
+                write!(f, "{int}.{frac}")
+            } else {
+                write!(f, "0.{}{digits}", "0".repeat(-point as usize))
+            }
+        } else {
+            let (first, rest) = digits.split_at(1);
+            f.write_str(first)?;
+            if !rest.is_empty() {
+                write!(f, ".{rest}")?;
+            }
+            write!(f, "E{adjusted:+}")
+        }
+    }
+}
+
+pub(crate) const MIN_EXPONENT: i32 = -6176;
+pub(crate) const MAX_EXPONENT: i32 = 6111;
+const MAX_DIGITS: usize = 34;
+
+impl Decimal128 {
+    pub const NAN: Decimal128 = Decimal128 {
+        bytes: 0x7C00_0000_0000_0000_0000_0000_0000_0000u128.to_le_bytes(),
+    };
+    pub const INFINITY: Decimal128 = Decimal128 {
+        bytes: 0x7800_0000_0000_0000_0000_0000_0000_0000u128.to_le_bytes(),
+    };
+    pub const NEG_INFINITY: Decimal128 = Decimal128 {
+        bytes: 0xF800_0000_0000_0000_0000_0000_0000_0000u128.to_le_bytes(),
+    };
+
+    /// encodes a finite value, `exponent` must be in range and `significand` at most 34 digits
+    pub(crate) fn from_parts(negative: bool, exponent: i32, significand: u128) -> Self {
+        let biased = (exponent + EXPONENT_BIAS) as u128;
+        let bits = ((negative as u128) << 127) | (biased << 113) | significand;
+        Self {
+            bytes: bits.to_le_bytes(),
+        }
+    }
+}
+
+impl std::str::FromStr for Decimal128 {
+    type Err = BsonError;
+
+    /// parses decimal strings like `-1.5E+3`, `Infinity` and `NaN`, values which can not be
+    /// represented exactly are rejected instead of rounded
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BsonError::custom_message(format!("invalid decimal128 string {s:?}"));
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if unsigned.eq_ignore_ascii_case("nan") {
+            return Ok(Decimal128::NAN);
+        }
+        if unsigned.eq_ignore_ascii_case("inf") || unsigned.eq_ignore_ascii_case("infinity") {
+            return Ok(if negative {
+                Decimal128::NEG_INFINITY
+            } else {
+                Decimal128::INFINITY
+            });
+        }
+
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => (&unsigned[..index], Some(&unsigned[index + 1..])),
+            None => (unsigned, None),
+        };
+        let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if int.len() + frac.len() == 0 || !all_digits(int) || !all_digits(frac) {
+            return Err(invalid());
+        }
+        let mut exponent = match exponent {
+            Some(exponent) => {
+                let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                if digits.is_empty() || !all_digits(digits) {
+                    return Err(invalid());
+                }
+                // anything this large is out of range whatever the digits are
+                exponent
+                    .parse::<i64>()
+                    .unwrap_or(if exponent.starts_with('-') {
+                        i64::MIN / 2
+                    } else {
+                        i64::MAX / 2
+                    })
+            }
+            None => 0,
+        };
+        exponent -= frac.len() as i64;
+
+        let mut digits: Vec<u8> = int.bytes().chain(frac.bytes()).map(|b| b - b'0').collect();
+        let leading_zeros = digits.iter().take_while(|d| **d == 0).count();
+        digits.drain(..leading_zeros);
+
+        // drop trailing zeros that do not fit, only exact results are accepted
+        while digits.len() > MAX_DIGITS || (exponent < MIN_EXPONENT as i64 && !digits.is_empty()) {
+            if digits.last() != Some(&0) {
+                return Err(invalid());
+            }
+            digits.pop();
+            exponent += 1;
+        }
+        if digits.is_empty() {
+            exponent = exponent.clamp(MIN_EXPONENT as i64, MAX_EXPONENT as i64);
+        }
+        // clamp large exponents by padding the coefficient with zeros
+        while exponent > MAX_EXPONENT as i64 && digits.len() < MAX_DIGITS {
+            digits.push(0);
+            exponent -= 1;
+        }
+        if exponent > MAX_EXPONENT as i64 {
+            return Err(invalid());
+        }
+
+        let significand = digits.iter().fold(0u128, |acc, d| acc * 10 + *d as u128);
+        Ok(Decimal128::from_parts(
+            negative,
+            exponent as i32,
+            significand,
+        ))
+    }
+}
+
+/// number of decimal digits of a coefficient, 0 counts as one digit
+fn digit_count(value: u128) -> u32 {
+    value.checked_ilog10().map_or(1, |log| log + 1)
+}
+
+/// compares the absolute values of two finite numbers given as (exponent, coefficient)
+fn compare_magnitude((e1, c1): (i32, u128), (e2, c2): (i32, u128)) -> Ordering {
+    match (c1 == 0, c2 == 0) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (false, false) => {}
+    }
+    let (d1, d2) = (digit_count(c1), digit_count(c2));
+    (e1 + d1 as i32).cmp(&(e2 + d2 as i32)).then_with(|| {
+        // same order of magnitude, pad the shorter coefficient so the digits line up
+        let (c1, c2) = match d1.cmp(&d2) {
+            Ordering::Less => (c1 * 10u128.pow(d2 - d1), c2),
+            Ordering::Greater => (c1, c2 * 10u128.pow(d1 - d2)),
+            Ordering::Equal => (c1, c2),
+        };
+        c1.cmp(&c2)
+    })
+}
+
+impl Decimal128 {
+    fn is_zero(&self) -> bool {
+        !self.is_nan() && !self.is_infinite() && self.parts().1 == 0
+    }
+
+    /// nearest `f64`, the same value parsing [`Decimal128::to_string`] would give
+    pub fn to_f64(&self) -> f64 {
+        if self.is_nan() {
+            return f64::NAN;
+        }
+        // NaN aside the string form is always a valid f64 literal
+        self.to_string().parse().unwrap()
+    }
+}
+
+impl PartialOrd for Decimal128 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.is_nan() || other.is_nan() {
+            return None;
+        }
+        let magnitude = match (self.is_infinite(), other.is_infinite()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => compare_magnitude(self.parts(), other.parts()),
+        };
+        let negative = |value: &Self| value.is_negative() && !value.is_zero();
+        Some(match (negative(self), negative(other)) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        })
+    }
+}
+
+impl PartialEq for Decimal128 {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl From<i32> for Decimal128 {
+    fn from(value: i32) -> Self {
+        Decimal128::from(value as i64)
+    }
+}
+
+impl From<i64> for Decimal128 {
+    fn from(value: i64) -> Self {
+        Decimal128::from_parts(value < 0, 0, value.unsigned_abs() as u128)
+    }
+}
+
+impl From<f64> for Decimal128 {
+    /// shortest decimal that converts back to the same `f64`, so `0.1` becomes `0.1`
+    fn from(value: f64) -> Self {
+        if value.is_nan() {
+            return Decimal128::NAN;
+        }
+        // at most 17 digits and exponents far inside the decimal128 range
+        format!("{value:e}").parse().unwrap()
+    }
+}
+
+impl TryFrom<Decimal128> for i64 {
+    type Error = BsonError;
+
+    /// succeeds only for integral values in range, `1.0E+2` is 100 but `1.5` is an error
+    fn try_from(value: Decimal128) -> Result<Self, Self::Error> {
+        let error = || BsonError::custom_message(format!("{value} is not representable as i64"));
+        if value.is_nan() || value.is_infinite() {
+            return Err(error());
+        }
+        let (exponent, significand) = value.parts();
+        let magnitude = if significand == 0 {
+            Some(0)
+        } else if exponent >= 0 {
+            10u128
+                .checked_pow(exponent as u32)
+                .and_then(|scale| significand.checked_mul(scale))
+        } else {
+            10u128
+                .checked_pow(exponent.unsigned_abs())
+                .filter(|scale| significand % scale == 0)
+                .map(|scale| significand / scale)
+        };
+        let magnitude = magnitude
+            .and_then(|magnitude| i128::try_from(magnitude).ok())
+            .ok_or_else(error)?;
+        let signed = if value.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        };
+        i64::try_from(signed).map_err(|_| error())
+    }
+}
+
+impl TryFrom<Decimal128> for f64 {
+    type Error = BsonError;
+
+    /// succeeds when the `f64` converts back to a decimal equal to `value`, so `0.1` is
+    /// accepted while `0.10000000000000000001` is not
+    fn try_from(value: Decimal128) -> Result<Self, Self::Error> {
+        let float = value.to_f64();
+        if value.is_nan() || Decimal128::from(float) == value {
+            Ok(float)
+        } else {
+            Err(BsonError::custom_message(format!(
+                "{value} is not representable as f64"
+            )))
+        }
+    }
+}
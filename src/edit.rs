@@ -0,0 +1,269 @@
+use std::ops::{Deref, DerefMut};
+
+use super::element::*;
+use super::encode::*;
+use super::error::*;
+use super::parse::*;
+
+/// location of one element inside a document body
+struct ElementSpan {
+    start: usize,
+    value_start: usize,
+    end: usize,
+    element: Element,
+}
+
+fn find_span(data: &[u8], key: &str) -> Result<Option<ElementSpan>, BsonError> {
+    let mut input = data;
+    while !input.is_empty() {
+        let start = data.len() - input.len();
+        let (rest, (ename, element)) = decode_element(data, input)?;
+        input = rest;
+        if ename == key {
+            return Ok(Some(ElementSpan {
+                start,
+                value_start: start + ename.len() + 2,
+                end: data.len() - input.len(),
+                element: element.to_owned(),
+            }));
+        }
+    }
+    Ok(None)
+}
+
+fn not_found(key: &str) -> BsonError {
+    BsonError::new(ErrorKind::KeyNotFound).with_key(key)
+}
+
+fn encoded_element(key: &str, element: &Element) -> Result<Vec<u8>, BsonError> {
+    check_cstring(key)
+        .and_then(|_| element.check_cstrings())
+        .map_err(|error| error.with_key(key))?;
+    let mut buf = vec![];
+    encode_element(&mut buf, key, element);
+    Ok(buf)
+}
+
+fn insert_element(
+    data: &mut Vec<u8>,
+    key: &str,
+    element: Element,
+) -> Result<Option<Element>, BsonError> {
+    let encoded = encoded_element(key, &element)?;
+    match find_span(data, key)? {
+        Some(span) => {
+            data.splice(span.start..span.end, encoded);
+            Ok(Some(span.element))
+        }
+        None => {
+            data.extend_from_slice(&encoded);
+            Ok(None)
+        }
+    }
+}
+
+fn remove_element(data: &mut Vec<u8>, key: &str) -> Result<Element, BsonError> {
+    let span = find_span(data, key)?.ok_or_else(|| not_found(key))?;
+    data.drain(span.start..span.end);
+    Ok(span.element)
+}
+
+/// rewrites the keys of an array body as "0", "1", ... keeping element order
+fn renumber(data: &[u8]) -> Result<Vec<u8>, BsonError> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut input = data;
+    let mut index = 0usize;
+    while !input.is_empty() {
+        let start = data.len() - input.len();
+        let (rest, (ename, _)) = decode_element(data, input)?;
+        let end = data.len() - rest.len();
+        out.push(data[start]);
+        encode_cstring(&mut out, &index.to_string());
+        out.extend_from_slice(&data[start + ename.len() + 2..end]);
+        input = rest;
+        index += 1;
+    }
+    Ok(out)
+}
+
+/// Mutable view of a nested document, written back into its parent on drop
+///
+/// ```rust
+/// use bson2::{doc, Element};
+///
+/// let mut doc = doc! { "outer": { "inner": 1 }, "after": true };
+/// let mut outer = doc.get_document_mut("outer").unwrap();
+/// outer.insert("inner", Element::Int32(2)).unwrap();
+/// drop(outer);
+/// assert_eq!(doc! { "outer": { "inner": 2 }, "after": true }, doc);
+/// ```
+#[derive(Debug)]
+pub struct DocumentMut<'a> {
+    parent: &'a mut Vec<u8>,
+    value_start: usize,
+    end: usize,
+    doc: Document,
+}
+
+/// Mutable view of a nested array, written back into its parent on drop
+#[derive(Debug)]
+pub struct ArrayMut<'a> {
+    parent: &'a mut Vec<u8>,
+    value_start: usize,
+    end: usize,
+    array: Array,
+}
+
+fn document_mut<'a>(parent: &'a mut Vec<u8>, key: &str) -> Result<DocumentMut<'a>, BsonError> {
+    let span = find_span(parent, key)?.ok_or_else(|| not_found(key))?;
+    match span.element {
+        Element::EmbededDocument(doc) => Ok(DocumentMut {
+            parent,
+            value_start: span.value_start,
+            end: span.end,
+            doc,
+        }),
+        other => Err(
+            BsonError::type_mismatch(ELEMENT_TYPE_EMBED_DOCUMENT, other.element_type())
+                .with_key(key),
+        ),
+    }
+}
+
+fn array_mut<'a>(parent: &'a mut Vec<u8>, key: &str) -> Result<ArrayMut<'a>, BsonError> {
+    let span = find_span(parent, key)?.ok_or_else(|| not_found(key))?;
+    match span.element {
+        Element::ArrayDocument(array) => Ok(ArrayMut {
+            parent,
+            value_start: span.value_start,
+            end: span.end,
+            array,
+        }),
+        other => Err(
+            BsonError::type_mismatch(ELEMENT_TYPE_ARRAY_DOCUMENT, other.element_type())
+                .with_key(key),
+        ),
+    }
+}
+
+impl<'a> Deref for DocumentMut<'a> {
+    type Target = Document;
+
+    fn deref(&self) -> &Self::Target {
+        &self.doc
+    }
+}
+
+impl<'a> DerefMut for DocumentMut<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.doc
+    }
+}
+
+impl<'a> Drop for DocumentMut<'a> {
+    fn drop(&mut self) {
+        self.parent
+            .splice(self.value_start..self.end, self.doc.to_bytes());
+    }
+}
+
+impl<'a> Deref for ArrayMut<'a> {
+    type Target = Array;
+
+    fn deref(&self) -> &Self::Target {
+        &self.array
+    }
+}
+
+impl<'a> DerefMut for ArrayMut<'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.array
+    }
+}
+
+impl<'a> Drop for ArrayMut<'a> {
+    fn drop(&mut self) {
+        self.parent
+            .splice(self.value_start..self.end, self.array.to_bytes());
+    }
+}
+
+impl Document {
+    /// sets `key` to `element`, replacing an existing value in place or appending a new field,
+    /// returns the replaced value
+    pub fn insert(&mut self, key: &str, element: Element) -> Result<Option<Element>, BsonError> {
+        insert_element(&mut self.data, key, element)
+    }
+
+    /// removes `key` and returns its value
+    pub fn remove(&mut self, key: &str) -> Result<Element, BsonError> {
+        remove_element(&mut self.data, key)
+    }
+
+    /// renames `old` to `new` keeping the field position
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), BsonError> {
+        check_cstring(new).map_err(|error| error.with_key(new))?;
+        if old == new {
+            return find_span(&self.data, old)?
+                .map(|_| ())
+                .ok_or_else(|| not_found(old));
+        }
+        if find_span(&self.data, new)?.is_some() {
+            return Err(BsonError::new(ErrorKind::DuplicateKey).with_key(new));
+        }
+        let span = find_span(&self.data, old)?.ok_or_else(|| not_found(old))?;
+        let mut header = vec![self.data[span.start]];
+        encode_cstring(&mut header, new);
+        self.data.splice(span.start..span.value_start, header);
+        Ok(())
+    }
+
+    pub fn get_document_mut(&mut self, key: &str) -> Result<DocumentMut<'_>, BsonError> {
+        document_mut(&mut self.data, key)
+    }
+
+    pub fn get_array_mut(&mut self, key: &str) -> Result<ArrayMut<'_>, BsonError> {
+        array_mut(&mut self.data, key)
+    }
+}
+
+impl Array {
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// appends `element` with the next index as key, panics on a regular expression containing
+    /// a null byte
+    pub fn push(&mut self, element: Element) {
+        let key = self.len().to_string();
+        encode_element(&mut self.data, &key, &element);
+    }
+
+    /// replaces the value at `index` and returns the old one
+    pub fn set(&mut self, index: usize, element: Element) -> Result<Element, BsonError> {
+        let key = index.to_string();
+        let span = find_span(&self.data, &key)?.ok_or_else(|| not_found(&key))?;
+        let encoded = encoded_element(&key, &element)?;
+        self.data.splice(span.start..span.end, encoded);
+        Ok(span.element)
+    }
+
+    /// removes the value at `index`, following values shift down by one
+    pub fn remove(&mut self, index: usize) -> Result<Element, BsonError> {
+        let element = remove_element(&mut self.data, &index.to_string())?;
+        self.data = renumber(&self.data)?;
+        Ok(element)
+    }
+
+    pub fn get_document_mut(&mut self, index: usize) -> Result<DocumentMut<'_>, BsonError> {
+        document_mut(&mut self.data, &index.to_string())
+    }
+
+    pub fn get_array_mut(&mut self, index: usize) -> Result<ArrayMut<'_>, BsonError> {
+        array_mut(&mut self.data, &index.to_string())
+    }
+}
@@ -1,28 +1,40 @@
+use std::sync::atomic::{AtomicU32, Ordering as AtomicOrdering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::datetime::*;
+use super::decimal128::*;
+use super::error::*;
 use super::parse::*;
+use super::timestamp::*;
+use super::uuid::random_bytes;
 
-pub type Decimal = [u8; 128 / 8];
-pub type DbPointer = [u8; 12];
-pub type JavascriptCode = (String, Document);
 pub type KeyPair<T> = (String, T);
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BinaryType {
-    BinaryGeneric = 0x00,
-    BinaryFunction = 0x01,
-    BinaryBinary = 0x02,
-    BinaryOldUuid = 0x03,
-    BinaryUuid = 0x04,
-    BinaryMd5 = 0x05,
-    BinaryEncrypted = 0x06,
-    BinaryCompressed = 0x07,
-    BinaryUserDefined = 0x80,
+    BinaryGeneric,
+    BinaryFunction,
+    /// deprecated, the encoded data repeats its own length which decoding strips
+    BinaryBinary,
+    BinaryOldUuid,
+    BinaryUuid,
+    BinaryMd5,
+    BinaryEncrypted,
+    BinaryCompressed,
+    /// data that should not be logged, `Debug` of a [`Binary`] hides it
+    BinarySensitive,
+    BinaryVector,
+    /// subtypes 0x0A to 0x7F, reserved for future use by the specification
+    BinaryReserved(u8),
+    /// subtypes 0x80 to 0xFF, free for applications to use
+    BinaryUserDefined(u8),
 }
 
-impl TryFrom<u8> for BinaryType {
-    type Error = BsonError;
-
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
-        let value = match value {
+impl From<u8> for BinaryType {
+    /// every subtype is accepted, unknown ones keep their byte
+    fn from(value: u8) -> Self {
+        match value {
             0x00 => BinaryType::BinaryGeneric,
             0x01 => BinaryType::BinaryFunction,
             0x02 => BinaryType::BinaryBinary,
@@ -31,20 +43,76 @@ impl TryFrom<u8> for BinaryType {
             0x05 => BinaryType::BinaryMd5,
             0x06 => BinaryType::BinaryEncrypted,
             0x07 => BinaryType::BinaryCompressed,
-            0x80 => BinaryType::BinaryUserDefined,
-            _ => todo!(),
-        };
-        Ok(value)
+            0x08 => BinaryType::BinarySensitive,
+            0x09 => BinaryType::BinaryVector,
+            0x0A..=0x7F => BinaryType::BinaryReserved(value),
+            0x80..=0xFF => BinaryType::BinaryUserDefined(value),
+        }
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl From<&BinaryType> for u8 {
+    fn from(value: &BinaryType) -> Self {
+        match value {
+            BinaryType::BinaryGeneric => 0x00,
+            BinaryType::BinaryFunction => 0x01,
+            BinaryType::BinaryBinary => 0x02,
+            BinaryType::BinaryOldUuid => 0x03,
+            BinaryType::BinaryUuid => 0x04,
+            BinaryType::BinaryMd5 => 0x05,
+            BinaryType::BinaryEncrypted => 0x06,
+            BinaryType::BinaryCompressed => 0x07,
+            BinaryType::BinarySensitive => 0x08,
+            BinaryType::BinaryVector => 0x09,
+            BinaryType::BinaryReserved(value) | BinaryType::BinaryUserDefined(value) => *value,
+        }
+    }
+}
+
+/// writes `data` of a binary, hiding it for the sensitive subtype
+pub(crate) fn debug_binary(
+    f: &mut std::fmt::Formatter,
+    name: &str,
+    binary_type: BinaryType,
+    data: &[u8],
+) -> std::fmt::Result {
+    let mut debug = f.debug_struct(name);
+    debug.field("binary_type", &binary_type);
+    if binary_type == BinaryType::BinarySensitive {
+        debug.field("data", &format_args!("<{} bytes redacted>", data.len()));
+    } else {
+        debug.field("data", &data);
+    }
+    debug.finish()
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Binary {
     pub binary_type: BinaryType,
     // todo replace with md5, uuid, ....
     pub data: Vec<u8>,
 }
 
+impl std::fmt::Debug for Binary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        debug_binary(f, "Binary", self.binary_type, &self.data)
+    }
+}
+
+/// JavaScript code with the variables it runs with (0x0F), deprecated by the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeWithScope {
+    pub code: String,
+    pub scope: Document,
+}
+
+/// Deprecated reference to a document of another collection, `namespace` is `db.collection`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbPointer {
+    pub namespace: String,
+    pub id: ObjectId,
+}
+
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct ObjectId {
     pub id: [u8; 12],
@@ -70,102 +138,244 @@ impl std::fmt::Debug for ObjectId {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// random part of generated ids with the process id it was drawn for, a forked child sees
+/// another process id and draws its own
+static PROCESS_UNIQUE: Mutex<Option<(u32, [u8; 5])>> = Mutex::new(None);
+static OBJECT_ID_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+fn process_unique() -> [u8; 5] {
+    let pid = std::process::id();
+    let mut state = PROCESS_UNIQUE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    match *state {
+        Some((owner, unique)) if owner == pid => unique,
+        _ => {
+            let random: [u8; 9] = random_bytes();
+            let unique = [random[0], random[1], random[2], random[3], random[4]];
+            let counter = u32::from_le_bytes([random[5], random[6], random[7], random[8]]);
+            OBJECT_ID_COUNTER.store(counter, AtomicOrdering::Relaxed);
+            *state = Some((pid, unique));
+            unique
+        }
+    }
+}
+
+impl ObjectId {
+    /// Generates a new id: 4 bytes of seconds since the epoch, 5 random bytes unique to the
+    /// process and a 3 byte counter starting at a random value
+    ///
+    /// ```rust
+    /// use bson2::ObjectId;
+    ///
+    /// let (first, second) = (ObjectId::new(), ObjectId::new());
+    /// assert_ne!(first, second);
+    /// assert_eq!(first.id[4..9], second.id[4..9]);
+    /// assert_eq!(Ok(first), first.to_string().parse());
+    /// ```
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as u32);
+        let unique = process_unique();
+        let counter = OBJECT_ID_COUNTER.fetch_add(1, AtomicOrdering::Relaxed);
+        let mut id = [0u8; 12];
+        id[..4].copy_from_slice(&seconds.to_be_bytes());
+        id[4..9].copy_from_slice(&unique);
+        id[9..].copy_from_slice(&counter.to_be_bytes()[1..]);
+        Self { id }
+    }
+
+    /// creation time stored in the first 4 bytes, whole seconds
+    pub fn timestamp(&self) -> DateTime {
+        let seconds = u32::from_be_bytes([self.id[0], self.id[1], self.id[2], self.id[3]]);
+        DateTime::from(seconds as i64 * 1000)
+    }
+
+    /// smallest id created in the second of `time`, a lower bound for `_id` range queries
+    pub fn min_for_time(time: DateTime) -> Self {
+        Self::for_time(time, 0x00)
+    }
+
+    /// largest id created in the second of `time`, an upper bound for `_id` range queries
+    pub fn max_for_time(time: DateTime) -> Self {
+        Self::for_time(time, 0xFF)
+    }
+
+    /// dates outside of what 4 bytes of seconds can hold are clamped to 1970 and 2106
+    fn for_time(time: DateTime, fill: u8) -> Self {
+        let seconds = time.millis.div_euclid(1000).clamp(0, u32::MAX as i64) as u32;
+        let mut id = [fill; 12];
+        id[..4].copy_from_slice(&seconds.to_be_bytes());
+        Self { id }
+    }
+}
+
+impl std::str::FromStr for ObjectId {
+    type Err = BsonError;
+
+    /// parses the 24 hexadecimal characters of [`ObjectId`]'s `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut id = [0u8; 12];
+        hex::decode_to_slice(s, &mut id)
+            .map_err(|_| BsonError::custom_message(format!("invalid object id {s:?}")))?;
+        Ok(Self { id })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Element {
     Double(f64),
     String(String),
     EmbededDocument(Document),
     ArrayDocument(Array),
     Binary(Binary),
-    Cstring(String, String),
     Undefined,
     ObjectId(ObjectId),
     Boolean(bool),
-    DateTime(i64),
+    DateTime(DateTime),
     Null,
     RegularExpression { pattern: String, options: String },
-    DbPointer([u8; 12]),
+    DbPointer(DbPointer),
     Javascript(String),
     Symbol(String),
-    JavascriptCode(String, Document),
+    JavascriptCode(CodeWithScope),
     Int32(i32),
-    Timestamp(u64),
+    Timestamp(Timestamp),
     Int64(i64),
-    Decimal(Decimal),
+    Decimal(Decimal128),
     Min,
     Max,
 }
 
+macro_rules! element_from {
+    ($type:ty, $element_type:path) => {
+        impl From<$type> for Element {
+            fn from(value: $type) -> Self {
+                $element_type(value.into())
+            }
+        }
+    };
+}
+
+element_from!(f64, Element::Double);
+element_from!(f32, Element::Double);
+element_from!(String, Element::String);
+element_from!(&str, Element::String);
+element_from!(Document, Element::EmbededDocument);
+element_from!(Array, Element::ArrayDocument);
+element_from!(Binary, Element::Binary);
+element_from!(ObjectId, Element::ObjectId);
+element_from!(bool, Element::Boolean);
+element_from!(i32, Element::Int32);
+element_from!(i64, Element::Int64);
+
+impl<T: Into<Element>> From<Option<T>> for Element {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Element::Null,
+        }
+    }
+}
+
 macro_rules! element_as {
-    ($func_name:ident, $type:ident, $element_type:path ) => {
+    ($func_name:ident, $type:ident, $element_type:path, $type_byte:expr) => {
         pub fn $func_name(self) -> Result<$type, BsonError> {
             match self {
                 $element_type(res) => Ok(res),
-                _ => Err(BsonError::Generic),
+                other => Err(BsonError::type_mismatch($type_byte, other.element_type())),
             }
         }
     };
 }
 impl Element {
-    element_as!(as_float, f64, Element::Double);
-    element_as!(as_string, String, Element::String);
-    element_as!(as_document, Document, Element::EmbededDocument);
-    element_as!(as_binary, Binary, Element::Binary);
-    element_as!(as_object_id, ObjectId, Element::ObjectId);
-    element_as!(as_bool, bool, Element::Boolean);
-    element_as!(as_datetime, i64, Element::DateTime);
-    element_as!(as_dbpointer, DbPointer, Element::DbPointer);
-    element_as!(as_javascript, String, Element::Javascript);
-    element_as!(as_symbol, String, Element::Symbol);
-    element_as!(as_array, Array, Element::ArrayDocument);
-    element_as!(as_int32, i32, Element::Int32);
-    element_as!(as_timestamp, u64, Element::Timestamp);
-    element_as!(as_i64, i64, Element::Int64);
-    element_as!(as_decimal128, Decimal, Element::Decimal);
+    element_as!(as_float, f64, Element::Double, ELEMENT_TYPE_DOUBLE);
+    element_as!(as_string, String, Element::String, ELEMENT_TYPE_STRING);
+    element_as!(
+        as_document,
+        Document,
+        Element::EmbededDocument,
+        ELEMENT_TYPE_EMBED_DOCUMENT
+    );
+    element_as!(as_binary, Binary, Element::Binary, ELEMENT_TYPE_BINARY);
+    element_as!(
+        as_object_id,
+        ObjectId,
+        Element::ObjectId,
+        ELEMENT_TYPE_OBJECT_ID
+    );
+    element_as!(as_bool, bool, Element::Boolean, ELEMENT_TYPE_BOOLEAN);
+    element_as!(
+        as_datetime,
+        DateTime,
+        Element::DateTime,
+        ELEMENT_TYPE_DATETIME
+    );
+    element_as!(
+        as_dbpointer,
+        DbPointer,
+        Element::DbPointer,
+        ELEMENT_TYPE_DBPOINTER
+    );
+    element_as!(
+        as_javascript,
+        String,
+        Element::Javascript,
+        ELEMENT_TYPE_JAVASCRIPTCODE
+    );
+    element_as!(
+        as_code_with_scope,
+        CodeWithScope,
+        Element::JavascriptCode,
+        ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE
+    );
+    element_as!(as_symbol, String, Element::Symbol, ELEMENT_TYPE_SYMBOL);
+    element_as!(
+        as_array,
+        Array,
+        Element::ArrayDocument,
+        ELEMENT_TYPE_ARRAY_DOCUMENT
+    );
+    element_as!(as_int32, i32, Element::Int32, ELEMENT_TYPE_INT32);
+    element_as!(
+        as_timestamp,
+        Timestamp,
+        Element::Timestamp,
+        ELEMENT_TYPE_TIMESTAMP
+    );
+    element_as!(as_i64, i64, Element::Int64, ELEMENT_TYPE_INT64);
+    element_as!(
+        as_decimal128,
+        Decimal128,
+        Element::Decimal,
+        ELEMENT_TYPE_DECIMAL128
+    );
 
     pub fn is_undefined(&self) -> Result<bool, BsonError> {
-        Ok(match self {
-            Element::Undefined => true,
-            _ => false,
-        })
+        Ok(matches!(self, Element::Undefined))
     }
 
     pub fn is_null(&self) -> Result<bool, BsonError> {
-        Ok(match self {
-            Element::Null => true,
-            _ => false,
-        })
+        Ok(matches!(self, Element::Null))
     }
 
     pub fn is_max(&self) -> Result<bool, BsonError> {
-        Ok(match self {
-            Element::Max => true,
-            _ => false,
-        })
+        Ok(matches!(self, Element::Max))
     }
 
     pub fn is_min(&self) -> Result<bool, BsonError> {
-        Ok(match self {
-            Element::Min => true,
-            _ => false,
-        })
+        Ok(matches!(self, Element::Min))
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub enum BsonError {
-    Generic,
-    ParseError,
-    KeyNotFound,
-    Utf8Error,
-}
-
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Document {
     pub data: Vec<u8>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Array {
     pub data: Vec<u8>,
 }
@@ -173,34 +383,36 @@ pub struct Array {
 fn find_value(input: &[u8], key: &str) -> Result<Element, BsonError> {
     let mut mut_input = input;
     while !mut_input.is_empty() {
-        let (input1, (ename, element)) = parse_any(mut_input).map_err(|_| BsonError::ParseError)?;
+        let (input1, (ename, element)) = decode_element(input, mut_input)?;
         mut_input = input1;
         if ename == key {
-            return Ok(element);
+            return Ok(element.to_owned());
         }
     }
-    Err(BsonError::KeyNotFound)
+    Err(BsonError::new(ErrorKind::KeyNotFound).with_key(key))
 }
 
 macro_rules! match_element_doc {
-    ($func_name:ident, $type:ident, $element_type:path ) => {
+    ($func_name:ident, $type:ident, $element_type:path, $type_byte:expr) => {
         pub fn $func_name(&self, key: &str) -> Result<$type, BsonError> {
             let value = self.get_value(key)?;
             match value {
                 $element_type(res) => Ok(res),
-                _ => Err(BsonError::Generic),
+                other => Err(BsonError::type_mismatch($type_byte, other.element_type())
+                    .with_key(&key.to_string())),
             }
         }
     };
 }
 
 macro_rules! match_element_arr {
-    ($func_name:ident, $type:ident, $element_type:path ) => {
+    ($func_name:ident, $type:ident, $element_type:path, $type_byte:expr) => {
         pub fn $func_name(&self, key: usize) -> Result<$type, BsonError> {
             let value = self.get_value(key)?;
             match value {
                 $element_type(res) => Ok(res),
-                _ => Err(BsonError::Generic),
+                other => Err(BsonError::type_mismatch($type_byte, other.element_type())
+                    .with_key(&key.to_string())),
             }
         }
     };
@@ -211,48 +423,82 @@ impl Document {
         find_value(&self.data, key)
     }
 
-    match_element_doc!(get_float, f64, Element::Double);
-    match_element_doc!(get_string, String, Element::String);
-    match_element_doc!(get_document, Document, Element::EmbededDocument);
-    match_element_doc!(get_array, Array, Element::ArrayDocument);
-    match_element_doc!(get_binary, Binary, Element::Binary);
-    match_element_doc!(get_object_id, ObjectId, Element::ObjectId);
-    match_element_doc!(get_bool, bool, Element::Boolean);
-    match_element_doc!(get_datetime, i64, Element::DateTime);
-    match_element_doc!(get_dbpointer, DbPointer, Element::DbPointer);
-    match_element_doc!(get_javascript, String, Element::Javascript);
-    match_element_doc!(get_symbol, String, Element::Symbol);
-    match_element_doc!(get_int32, i32, Element::Int32);
-    match_element_doc!(get_timestamp, u64, Element::Timestamp);
-    match_element_doc!(get_i64, i64, Element::Int64);
-    match_element_doc!(get_decimal128, Decimal, Element::Decimal);
+    match_element_doc!(get_float, f64, Element::Double, ELEMENT_TYPE_DOUBLE);
+    match_element_doc!(get_string, String, Element::String, ELEMENT_TYPE_STRING);
+    match_element_doc!(
+        get_document,
+        Document,
+        Element::EmbededDocument,
+        ELEMENT_TYPE_EMBED_DOCUMENT
+    );
+    match_element_doc!(
+        get_array,
+        Array,
+        Element::ArrayDocument,
+        ELEMENT_TYPE_ARRAY_DOCUMENT
+    );
+    match_element_doc!(get_binary, Binary, Element::Binary, ELEMENT_TYPE_BINARY);
+    match_element_doc!(
+        get_object_id,
+        ObjectId,
+        Element::ObjectId,
+        ELEMENT_TYPE_OBJECT_ID
+    );
+    match_element_doc!(get_bool, bool, Element::Boolean, ELEMENT_TYPE_BOOLEAN);
+    match_element_doc!(
+        get_datetime,
+        DateTime,
+        Element::DateTime,
+        ELEMENT_TYPE_DATETIME
+    );
+    match_element_doc!(
+        get_dbpointer,
+        DbPointer,
+        Element::DbPointer,
+        ELEMENT_TYPE_DBPOINTER
+    );
+    match_element_doc!(
+        get_javascript,
+        String,
+        Element::Javascript,
+        ELEMENT_TYPE_JAVASCRIPTCODE
+    );
+    match_element_doc!(
+        get_code_with_scope,
+        CodeWithScope,
+        Element::JavascriptCode,
+        ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE
+    );
+    match_element_doc!(get_symbol, String, Element::Symbol, ELEMENT_TYPE_SYMBOL);
+    match_element_doc!(get_int32, i32, Element::Int32, ELEMENT_TYPE_INT32);
+    match_element_doc!(
+        get_timestamp,
+        Timestamp,
+        Element::Timestamp,
+        ELEMENT_TYPE_TIMESTAMP
+    );
+    match_element_doc!(get_i64, i64, Element::Int64, ELEMENT_TYPE_INT64);
+    match_element_doc!(
+        get_decimal128,
+        Decimal128,
+        Element::Decimal,
+        ELEMENT_TYPE_DECIMAL128
+    );
 
     pub fn is_undefined(&self, key: &str) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Undefined => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Undefined))
     }
 
     pub fn is_null(&self, key: &str) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Null => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Null))
     }
 
     pub fn is_max(&self, key: &str) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Max => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Max))
     }
 
     pub fn is_min(&self, key: &str) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Min => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Min))
     }
 
     pub fn get_any(&self, key: &str) -> Result<Element, BsonError> {
@@ -260,7 +506,7 @@ impl Document {
     }
 
     pub fn iter<'a>(&'a self) -> DocumentIter<'a> {
-        return DocumentIter { doc: &self.data };
+        DocumentIter { doc: &self.data }
     }
 }
 
@@ -273,51 +519,85 @@ impl Array {
         self.get_value(key)
     }
 
-    match_element_arr!(get_float, f64, Element::Double);
-    match_element_arr!(get_string, String, Element::String);
-    match_element_arr!(get_document, Document, Element::EmbededDocument);
-    match_element_arr!(get_array, Array, Element::ArrayDocument);
-    match_element_arr!(get_binary, Binary, Element::Binary);
-    match_element_arr!(get_object_id, ObjectId, Element::ObjectId);
-    match_element_arr!(get_bool, bool, Element::Boolean);
-    match_element_arr!(get_datetime, i64, Element::DateTime);
-    match_element_arr!(get_javascript, String, Element::Javascript);
-    match_element_arr!(get_symbol, String, Element::Symbol);
-    match_element_arr!(get_dbpointer, DbPointer, Element::DbPointer);
-    match_element_arr!(get_int32, i32, Element::Int32);
-    match_element_arr!(get_timestamp, u64, Element::Timestamp);
-    match_element_arr!(get_i64, i64, Element::Int64);
-    match_element_arr!(get_decimal128, Decimal, Element::Decimal);
+    match_element_arr!(get_float, f64, Element::Double, ELEMENT_TYPE_DOUBLE);
+    match_element_arr!(get_string, String, Element::String, ELEMENT_TYPE_STRING);
+    match_element_arr!(
+        get_document,
+        Document,
+        Element::EmbededDocument,
+        ELEMENT_TYPE_EMBED_DOCUMENT
+    );
+    match_element_arr!(
+        get_array,
+        Array,
+        Element::ArrayDocument,
+        ELEMENT_TYPE_ARRAY_DOCUMENT
+    );
+    match_element_arr!(get_binary, Binary, Element::Binary, ELEMENT_TYPE_BINARY);
+    match_element_arr!(
+        get_object_id,
+        ObjectId,
+        Element::ObjectId,
+        ELEMENT_TYPE_OBJECT_ID
+    );
+    match_element_arr!(get_bool, bool, Element::Boolean, ELEMENT_TYPE_BOOLEAN);
+    match_element_arr!(
+        get_datetime,
+        DateTime,
+        Element::DateTime,
+        ELEMENT_TYPE_DATETIME
+    );
+    match_element_arr!(
+        get_javascript,
+        String,
+        Element::Javascript,
+        ELEMENT_TYPE_JAVASCRIPTCODE
+    );
+    match_element_arr!(
+        get_code_with_scope,
+        CodeWithScope,
+        Element::JavascriptCode,
+        ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE
+    );
+    match_element_arr!(get_symbol, String, Element::Symbol, ELEMENT_TYPE_SYMBOL);
+    match_element_arr!(
+        get_dbpointer,
+        DbPointer,
+        Element::DbPointer,
+        ELEMENT_TYPE_DBPOINTER
+    );
+    match_element_arr!(get_int32, i32, Element::Int32, ELEMENT_TYPE_INT32);
+    match_element_arr!(
+        get_timestamp,
+        Timestamp,
+        Element::Timestamp,
+        ELEMENT_TYPE_TIMESTAMP
+    );
+    match_element_arr!(get_i64, i64, Element::Int64, ELEMENT_TYPE_INT64);
+    match_element_arr!(
+        get_decimal128,
+        Decimal128,
+        Element::Decimal,
+        ELEMENT_TYPE_DECIMAL128
+    );
     pub fn is_undefined(&self, key: usize) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Undefined => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Undefined))
     }
 
     pub fn is_null(&self, key: usize) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Null => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Null))
     }
 
     pub fn is_max(&self, key: usize) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Max => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Max))
     }
 
     pub fn is_min(&self, key: usize) -> Result<bool, BsonError> {
-        Ok(match self.get_value(key)? {
-            Element::Min => true,
-            _ => false,
-        })
+        Ok(matches!(self.get_value(key)?, Element::Min))
     }
 
     pub fn iter<'a>(&'a self) -> DocumentIter<'a> {
-        return DocumentIter { doc: &self.data };
+        DocumentIter { doc: &self.data }
     }
 }
 
@@ -325,7 +605,7 @@ impl TryFrom<&[u8]> for Document {
     type Error = BsonError;
 
     fn try_from(input: &[u8]) -> Result<Self, Self::Error> {
-        let (_input, doc) = parse_document(input).map_err(|_| BsonError::Generic)?;
+        let (_input, doc) = parse_document(input).map_err(|err| parse_error(input, err, 0))?;
         Ok(doc)
     }
 }
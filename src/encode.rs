@@ -0,0 +1,201 @@
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+use super::timestamp::*;
+
+pub(crate) fn encode_i32(buf: &mut Vec<u8>, value: i32) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn encode_i64(buf: &mut Vec<u8>, value: i64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn encode_timestamp(buf: &mut Vec<u8>, value: Timestamp) {
+    buf.extend_from_slice(&u64::from(value).to_le_bytes());
+}
+
+pub(crate) fn encode_double(buf: &mut Vec<u8>, value: f64) {
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+pub(crate) fn encode_bool(buf: &mut Vec<u8>, value: bool) {
+    buf.push(value as u8);
+}
+
+pub(crate) fn encode_object_id(buf: &mut Vec<u8>, value: ObjectId) {
+    buf.extend_from_slice(&value.id);
+}
+
+pub(crate) fn encode_datetime(buf: &mut Vec<u8>, value: DateTime) {
+    encode_i64(buf, value.millis);
+}
+
+pub(crate) fn encode_decimal(buf: &mut Vec<u8>, value: Decimal128) {
+    buf.extend_from_slice(&value.bytes);
+}
+
+/// fails on a null byte, which would end the cstring early
+pub(crate) fn check_cstring(value: &str) -> Result<(), BsonError> {
+    if value.contains('\0') {
+        return Err(BsonError::new(ErrorKind::NullInCString));
+    }
+    Ok(())
+}
+
+/// panics on a null byte, values from callers are checked with [`check_cstring`] first
+pub(crate) fn encode_cstring(buf: &mut Vec<u8>, value: &str) {
+    assert!(
+        !value.contains('\0'),
+        "cstring {value:?} contains a null byte"
+    );
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(0);
+}
+
+/// strings are length prefixed and may contain null bytes
+pub(crate) fn encode_string(buf: &mut Vec<u8>, value: &str) {
+    encode_i32(buf, value.len() as i32 + 1);
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(0);
+}
+
+/// writes `data` framed as a document: int32 total size, the elements and the trailing 0x00
+pub(crate) fn encode_document(buf: &mut Vec<u8>, data: &[u8]) {
+    encode_i32(buf, data.len() as i32 + 5);
+    buf.extend_from_slice(data);
+    buf.push(0);
+}
+
+pub(crate) fn encode_binary(buf: &mut Vec<u8>, binary: &Binary) {
+    let size = binary.data.len() as i32;
+    if binary.binary_type == BinaryType::BinaryBinary {
+        // old binary repeats the length inside the data
+        encode_i32(buf, size + 4);
+        buf.push(u8::from(&binary.binary_type));
+        encode_i32(buf, size);
+    } else {
+        encode_i32(buf, size);
+        buf.push(u8::from(&binary.binary_type));
+    }
+    buf.extend_from_slice(&binary.data);
+}
+
+pub(crate) fn encode_javascript_with_scope(buf: &mut Vec<u8>, value: &CodeWithScope) {
+    // int32 total size + (int32 size + code + 0x00) + scope document
+    encode_i32(
+        buf,
+        4 + 4 + value.code.len() as i32 + 1 + value.scope.data.len() as i32 + 5,
+    );
+    encode_string(buf, &value.code);
+    encode_document(buf, &value.scope.data);
+}
+
+/// writes one element (type byte, key and value) as it appears inside a document
+///
+/// Panics if `key` or a regular expression contains a null byte, see
+/// [`Element::check_cstrings`].
+pub fn encode_element(buf: &mut Vec<u8>, key: &str, element: &Element) {
+    buf.push(element.element_type());
+    encode_cstring(buf, key);
+    element.encode_into(buf);
+}
+
+impl Element {
+    /// bson type byte written in front of the element name
+    pub fn element_type(&self) -> u8 {
+        match self {
+            Element::Double(_) => ELEMENT_TYPE_DOUBLE,
+            Element::String(_) => ELEMENT_TYPE_STRING,
+            Element::EmbededDocument(_) => ELEMENT_TYPE_EMBED_DOCUMENT,
+            Element::ArrayDocument(_) => ELEMENT_TYPE_ARRAY_DOCUMENT,
+            Element::Binary(_) => ELEMENT_TYPE_BINARY,
+            Element::Undefined => ELEMENT_TYPE_UNDEFINED,
+            Element::ObjectId(_) => ELEMENT_TYPE_OBJECT_ID,
+            Element::Boolean(_) => ELEMENT_TYPE_BOOLEAN,
+            Element::DateTime(_) => ELEMENT_TYPE_DATETIME,
+            Element::Null => ELEMENT_TYPE_NULL,
+            Element::RegularExpression { .. } => ELEMENT_TYPE_REGULAR_EXPRESSION,
+            Element::DbPointer(_) => ELEMENT_TYPE_DBPOINTER,
+            Element::Javascript(_) => ELEMENT_TYPE_JAVASCRIPTCODE,
+            Element::Symbol(_) => ELEMENT_TYPE_SYMBOL,
+            Element::JavascriptCode(_) => ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE,
+            Element::Int32(_) => ELEMENT_TYPE_INT32,
+            Element::Timestamp(_) => ELEMENT_TYPE_TIMESTAMP,
+            Element::Int64(_) => ELEMENT_TYPE_INT64,
+            Element::Decimal(_) => ELEMENT_TYPE_DECIMAL128,
+            Element::Min => ELEMENT_TYPE_MIN,
+            Element::Max => ELEMENT_TYPE_MAX,
+        }
+    }
+
+    /// fails if the value holds a cstring with a null byte, the pattern or options of a regular
+    /// expression, which can not be encoded
+    pub fn check_cstrings(&self) -> Result<(), BsonError> {
+        match self {
+            Element::RegularExpression { pattern, options } => {
+                check_cstring(pattern).and_then(|_| check_cstring(options))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// appends the encoded value (without type byte and name) to `buf`, panics on a regular
+    /// expression containing a null byte
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            Element::Double(value) => encode_double(buf, *value),
+            Element::String(value) | Element::Javascript(value) | Element::Symbol(value) => {
+                encode_string(buf, value)
+            }
+            Element::EmbededDocument(doc) => encode_document(buf, &doc.data),
+            Element::ArrayDocument(arr) => encode_document(buf, &arr.data),
+            Element::Binary(binary) => encode_binary(buf, binary),
+            Element::RegularExpression { pattern, options } => {
+                encode_cstring(buf, pattern);
+                encode_cstring(buf, options);
+            }
+            Element::Undefined | Element::Null | Element::Min | Element::Max => {}
+            Element::ObjectId(id) => encode_object_id(buf, *id),
+            Element::Boolean(value) => encode_bool(buf, *value),
+            Element::DateTime(value) => encode_datetime(buf, *value),
+            Element::Int64(value) => encode_i64(buf, *value),
+            Element::DbPointer(pointer) => {
+                encode_string(buf, &pointer.namespace);
+                encode_object_id(buf, pointer.id);
+            }
+            Element::JavascriptCode(code) => encode_javascript_with_scope(buf, code),
+            Element::Int32(value) => encode_i32(buf, *value),
+            Element::Timestamp(value) => encode_timestamp(buf, *value),
+            Element::Decimal(value) => encode_decimal(buf, *value),
+        }
+    }
+}
+
+impl Document {
+    /// encodes document with its length prefix and trailing null byte
+    ///
+    /// ```rust
+    /// use bson2::Document;
+    ///
+    /// let value: &[u8] = &[16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0];
+    /// let doc = Document::try_from(value).unwrap();
+    /// assert_eq!(value, doc.to_bytes());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.data.len() + 5);
+        encode_document(&mut buf, &self.data);
+        buf
+    }
+}
+
+impl Array {
+    /// encodes array with its length prefix and trailing null byte
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.data.len() + 5);
+        encode_document(&mut buf, &self.data);
+        buf
+    }
+}
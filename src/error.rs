@@ -0,0 +1,198 @@
+use super::parse::*;
+
+/// What went wrong, see [`BsonError`] for where
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// input ended before the document or element it announced
+    Truncated,
+    /// length prefix that is negative or too small for its content
+    BadLength(i32),
+    InvalidUtf8,
+    /// element type byte that is not part of the bson specification
+    UnknownType,
+    /// the value has another element type than the one requested
+    TypeMismatch {
+        expected: u8,
+        actual: u8,
+    },
+    /// array keys must be "0", "1", ... in order
+    BadArrayKey,
+    /// documents and arrays nested deeper than the validation limit
+    NestingTooDeep,
+    /// structurally invalid bytes not covered by a more specific kind
+    Malformed,
+    /// number outside the range of the requested numeric type
+    NumericOverflow {
+        expected: u8,
+    },
+    /// number the requested numeric type can not hold exactly, such as `1.5` as an int
+    PrecisionLoss {
+        expected: u8,
+    },
+    KeyNotFound,
+    DuplicateKey,
+    /// key or regular expression with a 0x00 byte, which would end its cstring early
+    NullInCString,
+    ExtendedJson(String),
+    Custom(String),
+}
+
+/// Error with the position it happened at
+///
+/// `offset` counts bytes from the start of the encoded document, including its length prefix,
+/// `path` is the dotted key of the element being read, `element_type` its type byte.
+///
+/// ```rust
+/// use bson2::{doc, ErrorKind};
+///
+/// let doc = doc! { "a": "text" };
+/// let error = doc.get_int32("a").unwrap_err();
+/// assert_eq!(ErrorKind::TypeMismatch { expected: 0x10, actual: 0x02 }, error.kind);
+/// assert_eq!("expected int, found string at `a`", error.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BsonError {
+    pub kind: ErrorKind,
+    pub offset: Option<usize>,
+    pub path: Option<String>,
+    pub element_type: Option<u8>,
+}
+
+impl BsonError {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            offset: None,
+            path: None,
+            element_type: None,
+        }
+    }
+
+    pub(crate) fn custom_message(message: String) -> Self {
+        Self::new(ErrorKind::Custom(message))
+    }
+
+    pub(crate) fn extended_json(message: String) -> Self {
+        Self::new(ErrorKind::ExtendedJson(message))
+    }
+
+    pub(crate) fn type_mismatch(expected: u8, actual: u8) -> Self {
+        Self::new(ErrorKind::TypeMismatch { expected, actual })
+    }
+
+    pub(crate) fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub(crate) fn with_key(mut self, key: &str) -> Self {
+        self.path = Some(key.to_string());
+        self
+    }
+
+    pub(crate) fn with_element_type(mut self, element_type: u8) -> Self {
+        self.element_type = Some(element_type);
+        self
+    }
+
+    /// moves an error raised inside the value of `key` into the coordinates of the parent,
+    /// `value_offset` being where that value starts in the parent
+    pub(crate) fn nested(mut self, key: &str, value_offset: usize) -> Self {
+        self.path = Some(match self.path {
+            Some(path) => format!("{key}.{path}"),
+            None => key.to_string(),
+        });
+        self.offset = self.offset.map(|offset| offset + value_offset);
+        self
+    }
+}
+
+impl From<ErrorKind> for BsonError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+/// type alias used by the mongodb `$type` operator
+pub fn element_type_name(element_type: u8) -> &'static str {
+    match element_type {
+        ELEMENT_TYPE_DOUBLE => "double",
+        ELEMENT_TYPE_STRING => "string",
+        ELEMENT_TYPE_EMBED_DOCUMENT => "object",
+        ELEMENT_TYPE_ARRAY_DOCUMENT => "array",
+        ELEMENT_TYPE_BINARY => "binData",
+        ELEMENT_TYPE_UNDEFINED => "undefined",
+        ELEMENT_TYPE_OBJECT_ID => "objectId",
+        ELEMENT_TYPE_BOOLEAN => "bool",
+        ELEMENT_TYPE_DATETIME => "date",
+        ELEMENT_TYPE_NULL => "null",
+        ELEMENT_TYPE_REGULAR_EXPRESSION => "regex",
+        ELEMENT_TYPE_DBPOINTER => "dbPointer",
+        ELEMENT_TYPE_JAVASCRIPTCODE => "javascript",
+        ELEMENT_TYPE_SYMBOL => "symbol",
+        ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE => "javascriptWithScope",
+        ELEMENT_TYPE_INT32 => "int",
+        ELEMENT_TYPE_TIMESTAMP => "timestamp",
+        ELEMENT_TYPE_INT64 => "long",
+        ELEMENT_TYPE_DECIMAL128 => "decimal",
+        ELEMENT_TYPE_MIN => "minKey",
+        ELEMENT_TYPE_MAX => "maxKey",
+        _ => "unknown",
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ErrorKind::Truncated => f.write_str("unexpected end of input"),
+            ErrorKind::BadLength(length) => write!(f, "invalid length {length}"),
+            ErrorKind::InvalidUtf8 => f.write_str("invalid utf-8"),
+            ErrorKind::UnknownType => f.write_str("unknown element type"),
+            ErrorKind::TypeMismatch { expected, actual } => write!(
+                f,
+                "expected {}, found {}",
+                element_type_name(*expected),
+                element_type_name(*actual)
+            ),
+            ErrorKind::BadArrayKey => f.write_str("array keys out of sequence"),
+            ErrorKind::NestingTooDeep => f.write_str("nesting too deep"),
+            ErrorKind::Malformed => f.write_str("malformed bson"),
+            ErrorKind::NumericOverflow { expected } => {
+                write!(
+                    f,
+                    "number out of range for {}",
+                    element_type_name(*expected)
+                )
+            }
+            ErrorKind::PrecisionLoss { expected } => write!(
+                f,
+                "number not exactly representable as {}",
+                element_type_name(*expected)
+            ),
+            ErrorKind::KeyNotFound => f.write_str("key not found"),
+            ErrorKind::DuplicateKey => f.write_str("key already exists"),
+            ErrorKind::NullInCString => f.write_str("cstring contains a null byte"),
+            ErrorKind::ExtendedJson(message) => write!(f, "invalid extended json: {message}"),
+            ErrorKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::fmt::Display for BsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(path) = &self.path {
+            write!(f, " at `{path}`")?;
+        }
+        match (self.offset, self.element_type) {
+            (Some(offset), Some(element_type)) => {
+                write!(f, " (offset {offset}, element type 0x{element_type:02x})")
+            }
+            (Some(offset), None) => write!(f, " (offset {offset})"),
+            (None, Some(element_type)) => write!(f, " (element type 0x{element_type:02x})"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for BsonError {}
@@ -0,0 +1,976 @@
+use std::fmt::Write;
+
+use base64::Engine;
+
+use super::builder::*;
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::regular_expression::*;
+use super::timestamp::*;
+
+/// Flavour of [MongoDB Extended JSON v2](https://www.mongodb.com/docs/manual/reference/mongodb-extended-json/)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendedJsonMode {
+    /// keeps every bson type, numbers are wrapped as `{"$numberInt": "1"}`
+    Canonical,
+    /// native json numbers and iso-8601 dates where no information is lost
+    Relaxed,
+}
+
+/// last millisecond of 9999-12-31, relaxed `$date` strings only cover years 1970 to 9999
+const MAX_RELAXED_DATETIME: i64 = 253_402_300_799_999;
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// shortest representation that round-trips, `1.0` and `1.2345678921232E+18` style
+pub(crate) fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let debug = format!("{value:?}");
+    match debug.split_once('e') {
+        Some((mantissa, exponent)) => {
+            let mantissa = if mantissa.contains('.') {
+                mantissa.to_string()
+            } else {
+                format!("{mantissa}.0")
+            };
+            let sign = if exponent.starts_with('-') { "" } else { "+" };
+            format!("{mantissa}E{sign}{exponent}")
+        }
+        None => debug,
+    }
+}
+
+fn write_wrapped(out: &mut String, key: &str, value: &str) {
+    out.push('{');
+    write_string(out, key);
+    out.push_str(": ");
+    out.push_str(value);
+    out.push('}');
+}
+
+fn write_wrapped_string(out: &mut String, key: &str, value: &str) {
+    let mut quoted = String::new();
+    write_string(&mut quoted, value);
+    write_wrapped(out, key, &quoted);
+}
+
+fn write_elements(
+    out: &mut String,
+    elements: impl Iterator<Item = KeyPair<Element>>,
+    mode: ExtendedJsonMode,
+    array: bool,
+) {
+    out.push(if array { '[' } else { '{' });
+    for (index, (key, element)) in elements.enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        if !array {
+            write_string(out, &key);
+            out.push_str(": ");
+        }
+        write_element(out, &element, mode);
+    }
+    out.push(if array { ']' } else { '}' });
+}
+
+fn write_element(out: &mut String, element: &Element, mode: ExtendedJsonMode) {
+    let relaxed = mode == ExtendedJsonMode::Relaxed;
+    match element {
+        Element::Double(value) if relaxed && value.is_finite() => {
+            out.push_str(&format_double(*value))
+        }
+        Element::Double(value) => {
+            write_wrapped_string(out, "$numberDouble", &format_double(*value))
+        }
+        Element::String(value) => write_string(out, value),
+        Element::EmbededDocument(doc) => write_elements(out, doc.iter(), mode, false),
+        Element::ArrayDocument(array) => write_elements(out, array.iter(), mode, true),
+        Element::Binary(binary) => {
+            out.push_str("{\"$binary\": {\"base64\": ");
+            write_string(
+                out,
+                &base64::engine::general_purpose::STANDARD.encode(&binary.data),
+            );
+            let _ = write!(
+                out,
+                ", \"subType\": \"{:02x}\"}}}}",
+                u8::from(&binary.binary_type)
+            );
+        }
+        Element::RegularExpression { pattern, options } => {
+            out.push_str("{\"$regularExpression\": {\"pattern\": ");
+            write_string(out, pattern);
+            out.push_str(", \"options\": ");
+            write_string(out, options);
+            out.push_str("}}");
+        }
+        Element::Undefined => write_wrapped(out, "$undefined", "true"),
+        Element::ObjectId(id) => write_wrapped_string(out, "$oid", &id.to_string()),
+        Element::Boolean(value) => out.push_str(if *value { "true" } else { "false" }),
+        Element::DateTime(date) if relaxed && (0..=MAX_RELAXED_DATETIME).contains(&date.millis) => {
+            write_wrapped_string(out, "$date", &date.to_iso8601())
+        }
+        Element::DateTime(date) => {
+            let mut long = String::new();
+            write_wrapped_string(&mut long, "$numberLong", &date.millis.to_string());
+            write_wrapped(out, "$date", &long);
+        }
+        Element::Null => out.push_str("null"),
+        Element::DbPointer(pointer) => {
+            out.push_str("{\"$dbPointer\": {\"$ref\": ");
+            write_string(out, &pointer.namespace);
+            out.push_str(", \"$id\": ");
+            write_wrapped_string(out, "$oid", &pointer.id.to_string());
+            out.push_str("}}");
+        }
+        Element::Javascript(code) => write_wrapped_string(out, "$code", code),
+        Element::Symbol(symbol) => write_wrapped_string(out, "$symbol", symbol),
+        Element::JavascriptCode(value) => {
+            out.push_str("{\"$code\": ");
+            write_string(out, &value.code);
+            out.push_str(", \"$scope\": ");
+            write_elements(out, value.scope.iter(), mode, false);
+            out.push('}');
+        }
+        Element::Int32(value) if relaxed => out.push_str(&value.to_string()),
+        Element::Int32(value) => write_wrapped_string(out, "$numberInt", &value.to_string()),
+        Element::Int64(value) if relaxed => out.push_str(&value.to_string()),
+        Element::Int64(value) => write_wrapped_string(out, "$numberLong", &value.to_string()),
+        Element::Timestamp(value) => {
+            let _ = write!(
+                out,
+                "{{\"$timestamp\": {{\"t\": {}, \"i\": {}}}}}",
+                value.time, value.increment
+            );
+        }
+        Element::Decimal(value) => write_wrapped_string(out, "$numberDecimal", &value.to_string()),
+        Element::Min => write_wrapped(out, "$minKey", "1"),
+        Element::Max => write_wrapped(out, "$maxKey", "1"),
+    }
+}
+
+impl Element {
+    pub fn to_extended_json(&self, mode: ExtendedJsonMode) -> String {
+        let mut out = String::new();
+        write_element(&mut out, self, mode);
+        out
+    }
+}
+
+impl Document {
+    /// Extended JSON v2 text of the document
+    ///
+    /// ```rust
+    /// use bson2::{doc, ExtendedJsonMode};
+    ///
+    /// let doc = doc! { "int": 1, "long": 2i64, "list": [1.5] };
+    /// assert_eq!(
+    ///     r#"{"int": {"$numberInt": "1"}, "long": {"$numberLong": "2"}, "list": [{"$numberDouble": "1.5"}]}"#,
+    ///     doc.to_extended_json(ExtendedJsonMode::Canonical)
+    /// );
+    /// assert_eq!(
+    ///     r#"{"int": 1, "long": 2, "list": [1.5]}"#,
+    ///     doc.to_extended_json(ExtendedJsonMode::Relaxed)
+    /// );
+    /// ```
+    pub fn to_extended_json(&self, mode: ExtendedJsonMode) -> String {
+        let mut out = String::new();
+        write_elements(&mut out, self.iter(), mode, false);
+        out
+    }
+}
+
+impl Array {
+    pub fn to_extended_json(&self, mode: ExtendedJsonMode) -> String {
+        let mut out = String::new();
+        write_elements(&mut out, self.iter(), mode, true);
+        out
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.to_extended_json(ExtendedJsonMode::Relaxed))
+    }
+}
+
+impl std::fmt::Display for Array {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.to_extended_json(ExtendedJsonMode::Relaxed))
+    }
+}
+
+/// nesting limit of the json reader, deeper input is rejected instead of overflowing the stack
+const MAX_JSON_DEPTH: usize = 200;
+
+/// json value as read from the text, numbers keep their source representation
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn kind(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "a boolean",
+            Json::Number(_) => "a number",
+            Json::String(_) => "a string",
+            Json::Array(_) => "an array",
+            Json::Object(_) => "an object",
+        }
+    }
+}
+
+struct JsonReader<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn error(&self, message: &str) -> BsonError {
+        let consumed = &self.input[..self.pos];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed.chars().rev().take_while(|c| *c != '\n').count() + 1;
+        BsonError::extended_json(format!("{message} at line {line} column {column}"))
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), BsonError> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected `{}`", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, BsonError> {
+        if !self.input[self.pos..].starts_with(word) {
+            return Err(self.error("expected a json value"));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, BsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(_) => Err(self.error("expected a json value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        read: fn(&mut Self) -> Result<Json, BsonError>,
+    ) -> Result<Json, BsonError> {
+        if self.depth == MAX_JSON_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, BsonError> {
+        self.expect(b'{')?;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, BsonError> {
+        self.expect(b'[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, BsonError> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap())
+    }
+
+    fn string(&mut self) -> Result<String, BsonError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            out.push_str(&self.input[start..self.pos]);
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    let escaped = self.peek();
+                    self.pos += 1;
+                    match escaped {
+                        Some(b'"') => out.push('"'),
+                        Some(b'\\') => out.push('\\'),
+                        Some(b'/') => out.push('/'),
+                        Some(b'b') => out.push('\u{08}'),
+                        Some(b'f') => out.push('\u{0C}'),
+                        Some(b'n') => out.push('\n'),
+                        Some(b'r') => out.push('\r'),
+                        Some(b't') => out.push('\t'),
+                        Some(b'u') => out.push(self.unicode_escape()?),
+                        _ => {
+                            self.pos -= 1;
+                            return Err(self.error("invalid escape sequence"));
+                        }
+                    }
+                }
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, BsonError> {
+        let first = self.hex4()?;
+        let code = match first {
+            0xD800..=0xDBFF => {
+                if !self.input[self.pos..].starts_with("\\u") {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                self.pos += 2;
+                let second = self.hex4()?;
+                if !(0xDC00..=0xDFFF).contains(&second) {
+                    return Err(self.error("unpaired surrogate in unicode escape"));
+                }
+                0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+            }
+            0xDC00..=0xDFFF => return Err(self.error("unpaired surrogate in unicode escape")),
+            code => code,
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<Json, BsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let integer_start = self.pos;
+        let integer = self.digits();
+        if integer == 0 || (integer > 1 && self.input.as_bytes()[integer_start] == b'0') {
+            return Err(self.error("invalid number"));
+        }
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            if self.digits() == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.digits() == 0 {
+                return Err(self.error("invalid number"));
+            }
+        }
+        Ok(Json::Number(self.input[start..self.pos].to_string()))
+    }
+}
+
+/// error for a malformed value at dotted `path`
+fn invalid(path: &str, message: &str) -> BsonError {
+    if path.is_empty() {
+        BsonError::extended_json(message.to_string())
+    } else {
+        BsonError::extended_json(format!("{message} at `{path}`"))
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// keys that turn an object into a typed value instead of an embedded document
+const WRAPPER_KEYS: &[&str] = &[
+    "$oid",
+    "$symbol",
+    "$numberInt",
+    "$numberLong",
+    "$numberDouble",
+    "$numberDecimal",
+    "$binary",
+    "$uuid",
+    "$code",
+    "$timestamp",
+    "$regularExpression",
+    "$dbPointer",
+    "$date",
+    "$minKey",
+    "$maxKey",
+    "$undefined",
+];
+
+/// fields of a `$`-wrapper, checked against the exact set of keys it allows
+struct Wrapper<'a> {
+    name: &'a str,
+    path: &'a str,
+    fields: Vec<(String, Json)>,
+}
+
+impl<'a> Wrapper<'a> {
+    fn error(&self, message: &str) -> BsonError {
+        invalid(self.path, &format!("{}: {message}", self.name))
+    }
+
+    fn expect_keys(&self, keys: &[&str]) -> Result<(), BsonError> {
+        for (key, _) in &self.fields {
+            if !keys.contains(&key.as_str()) {
+                return Err(self.error(&format!("unexpected field `{key}`")));
+            }
+        }
+        for key in keys {
+            match self.fields.iter().filter(|(k, _)| k == key).count() {
+                0 => return Err(self.error(&format!("missing field `{key}`"))),
+                1 => {}
+                _ => return Err(self.error(&format!("duplicate field `{key}`"))),
+            }
+        }
+        Ok(())
+    }
+
+    fn take(&mut self, key: &str) -> Json {
+        let index = self.fields.iter().position(|(k, _)| k == key).unwrap();
+        self.fields.remove(index).1
+    }
+
+    fn take_string(&mut self, key: &str) -> Result<String, BsonError> {
+        match self.take(key) {
+            Json::String(value) => Ok(value),
+            other => Err(self.error(&format!("`{key}` must be a string, found {}", other.kind()))),
+        }
+    }
+
+    fn take_u32(&mut self, key: &str) -> Result<u32, BsonError> {
+        match self.take(key) {
+            Json::Number(number) => number
+                .parse()
+                .map_err(|_| self.error(&format!("`{key}` must be an unsigned 32-bit integer"))),
+            other => Err(self.error(&format!("`{key}` must be a number, found {}", other.kind()))),
+        }
+    }
+}
+
+fn parse_object_id(wrapper: &Wrapper, hex_id: &str) -> Result<ObjectId, BsonError> {
+    let mut id = [0u8; 12];
+    hex::decode_to_slice(hex_id, &mut id)
+        .map_err(|_| wrapper.error("expected 24 hexadecimal characters"))?;
+    Ok(ObjectId::from(id))
+}
+
+fn parse_binary_type(wrapper: &Wrapper, sub_type: &str) -> Result<BinaryType, BsonError> {
+    if sub_type.is_empty() || sub_type.len() > 2 {
+        return Err(wrapper.error("subType must be one or two hexadecimal characters"));
+    }
+    let sub_type = u8::from_str_radix(sub_type, 16)
+        .map_err(|_| wrapper.error("subType must be one or two hexadecimal characters"))?;
+    Ok(BinaryType::from(sub_type))
+}
+
+fn decode_base64(wrapper: &Wrapper, data: &str) -> Result<Vec<u8>, BsonError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .map_err(|_| wrapper.error("invalid base64 payload"))
+}
+
+/// name of the `$`-wrapper an object stands for, `None` for plain documents
+fn wrapper_name(fields: &[(String, Json)]) -> Option<&'static str> {
+    if let Some(name) = WRAPPER_KEYS
+        .iter()
+        .find(|name| fields.iter().any(|(key, _)| key == *name))
+    {
+        return Some(name);
+    }
+    // legacy `{"$regex": "..", "$options": ".."}`, `$regex` also is a query operator
+    let legacy_regex = fields.len() == 2
+        && fields[0].0 != fields[1].0
+        && fields.iter().all(|(key, value)| {
+            (key == "$regex" || key == "$options") && matches!(value, Json::String(_))
+        });
+    legacy_regex.then_some("$regex")
+}
+
+fn wrapper_object<'a>(wrapper: &mut Wrapper<'a>, key: &str) -> Result<Wrapper<'a>, BsonError> {
+    match wrapper.take(key) {
+        Json::Object(fields) => Ok(Wrapper {
+            name: wrapper.name,
+            path: wrapper.path,
+            fields,
+        }),
+        other => Err(wrapper.error(&format!("expected an object, found {}", other.kind()))),
+    }
+}
+
+/// converts an object carrying the `name` wrapper key into its typed value
+fn wrapped_element(
+    name: &str,
+    fields: Vec<(String, Json)>,
+    path: &str,
+) -> Result<Element, BsonError> {
+    let has = |key: &str| fields.iter().any(|(k, _)| k == key);
+    let (has_type, has_scope) = (has("$type"), has("$scope"));
+    let mut wrapper = Wrapper { name, path, fields };
+
+    let element = match name {
+        "$oid" => {
+            wrapper.expect_keys(&["$oid"])?;
+            let id = wrapper.take_string("$oid")?;
+            Element::ObjectId(parse_object_id(&wrapper, &id)?)
+        }
+        "$symbol" => {
+            wrapper.expect_keys(&["$symbol"])?;
+            Element::Symbol(wrapper.take_string("$symbol")?)
+        }
+        "$numberInt" => {
+            wrapper.expect_keys(&["$numberInt"])?;
+            let value = wrapper.take_string("$numberInt")?;
+            Element::Int32(
+                parse_integer(&value).ok_or_else(|| wrapper.error("expected a 32-bit integer"))?,
+            )
+        }
+        "$numberLong" => {
+            wrapper.expect_keys(&["$numberLong"])?;
+            let value = wrapper.take_string("$numberLong")?;
+            Element::Int64(
+                parse_integer(&value).ok_or_else(|| wrapper.error("expected a 64-bit integer"))?,
+            )
+        }
+        "$numberDouble" => {
+            wrapper.expect_keys(&["$numberDouble"])?;
+            let value = wrapper.take_string("$numberDouble")?;
+            Element::Double(parse_double(&value).ok_or_else(|| wrapper.error("expected a double"))?)
+        }
+        "$numberDecimal" => {
+            wrapper.expect_keys(&["$numberDecimal"])?;
+            let value = wrapper.take_string("$numberDecimal")?;
+            let decimal: Decimal128 = value
+                .parse()
+                .map_err(|_| wrapper.error("expected a decimal128 string"))?;
+            Element::Decimal(decimal)
+        }
+        "$binary" if has_type => {
+            // legacy `{"$binary": "<base64>", "$type": "<hex>"}`
+            wrapper.expect_keys(&["$binary", "$type"])?;
+            let data = wrapper.take_string("$binary")?;
+            let sub_type = wrapper.take_string("$type")?;
+            Element::Binary(Binary {
+                binary_type: parse_binary_type(&wrapper, &sub_type)?,
+                data: decode_base64(&wrapper, &data)?,
+            })
+        }
+        "$binary" => {
+            wrapper.expect_keys(&["$binary"])?;
+            let mut inner = wrapper_object(&mut wrapper, "$binary")?;
+            inner.expect_keys(&["base64", "subType"])?;
+            let data = inner.take_string("base64")?;
+            let sub_type = inner.take_string("subType")?;
+            Element::Binary(Binary {
+                binary_type: parse_binary_type(&inner, &sub_type)?,
+                data: decode_base64(&inner, &data)?,
+            })
+        }
+        "$uuid" => {
+            wrapper.expect_keys(&["$uuid"])?;
+            let uuid = wrapper.take_string("$uuid")?;
+            let dashes = [8, 13, 18, 23];
+            let well_formed = uuid.len() == 36
+                && uuid
+                    .char_indices()
+                    .all(|(i, c)| (c == '-') == dashes.contains(&i));
+            let data = hex::decode(uuid.replace('-', ""))
+                .ok()
+                .filter(|_| well_formed)
+                .ok_or_else(|| wrapper.error("expected a hyphenated uuid string"))?;
+            Element::Binary(Binary {
+                binary_type: BinaryType::BinaryUuid,
+                data,
+            })
+        }
+        "$code" if has_scope => {
+            wrapper.expect_keys(&["$code", "$scope"])?;
+            let code = wrapper.take_string("$code")?;
+            let scope = match wrapper.take("$scope") {
+                Json::Object(fields) => json_document(fields, &child_path(path, "$scope"))?,
+                other => {
+                    return Err(wrapper.error(&format!(
+                        "`$scope` must be an object, found {}",
+                        other.kind()
+                    )))
+                }
+            };
+            Element::JavascriptCode(CodeWithScope { code, scope })
+        }
+        "$code" => {
+            wrapper.expect_keys(&["$code"])?;
+            Element::Javascript(wrapper.take_string("$code")?)
+        }
+        "$timestamp" => {
+            wrapper.expect_keys(&["$timestamp"])?;
+            let mut inner = wrapper_object(&mut wrapper, "$timestamp")?;
+            inner.expect_keys(&["t", "i"])?;
+            let time = inner.take_u32("t")?;
+            let increment = inner.take_u32("i")?;
+            Element::Timestamp(Timestamp { time, increment })
+        }
+        "$regularExpression" => {
+            wrapper.expect_keys(&["$regularExpression"])?;
+            let mut inner = wrapper_object(&mut wrapper, "$regularExpression")?;
+            inner.expect_keys(&["pattern", "options"])?;
+            let pattern = inner.take_string("pattern")?;
+            let options = inner.take_string("options")?;
+            regular_expression(&inner, pattern, options)?
+        }
+        "$regex" => {
+            let pattern = wrapper.take_string("$regex")?;
+            let options = wrapper.take_string("$options")?;
+            regular_expression(&wrapper, pattern, options)?
+        }
+        "$dbPointer" => {
+            wrapper.expect_keys(&["$dbPointer"])?;
+            let mut inner = wrapper_object(&mut wrapper, "$dbPointer")?;
+            inner.expect_keys(&["$ref", "$id"])?;
+            let namespace = inner.take_string("$ref")?;
+            match inner.take("$id") {
+                Json::Object(fields) if wrapper_name(&fields) == Some("$oid") => {
+                    match wrapped_element("$oid", fields, path)? {
+                        Element::ObjectId(id) => Element::DbPointer(DbPointer { namespace, id }),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => return Err(inner.error("`$id` must be an $oid")),
+            }
+        }
+        "$date" => {
+            wrapper.expect_keys(&["$date"])?;
+            let millis = match wrapper.take("$date") {
+                Json::String(iso) => {
+                    DateTime::from_iso8601(&iso)
+                        .ok_or_else(|| wrapper.error("expected an iso-8601 date"))?
+                        .millis
+                }
+                Json::Object(fields) if wrapper_name(&fields) == Some("$numberLong") => {
+                    match wrapped_element("$numberLong", fields, path)? {
+                        Element::Int64(millis) => millis,
+                        _ => unreachable!(),
+                    }
+                }
+                other => {
+                    return Err(wrapper.error(&format!(
+                        "expected a string or an object, found {}",
+                        other.kind()
+                    )))
+                }
+            };
+            Element::DateTime(millis.into())
+        }
+        "$minKey" | "$maxKey" => {
+            wrapper.expect_keys(&[name])?;
+            match wrapper.take(name) {
+                Json::Number(one) if one == "1" => {}
+                _ => return Err(wrapper.error("value must be 1")),
+            }
+            if name == "$minKey" {
+                Element::Min
+            } else {
+                Element::Max
+            }
+        }
+        "$undefined" => {
+            wrapper.expect_keys(&["$undefined"])?;
+            match wrapper.take("$undefined") {
+                Json::Bool(true) => Element::Undefined,
+                _ => return Err(wrapper.error("value must be true")),
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(element)
+}
+
+fn regular_expression(
+    wrapper: &Wrapper,
+    pattern: String,
+    options: String,
+) -> Result<Element, BsonError> {
+    if pattern.contains('\0') || options.contains('\0') {
+        return Err(wrapper.error("pattern and options must not contain null bytes"));
+    }
+    Regex::new(pattern, &options)
+        .map(Element::from)
+        .map_err(|_| wrapper.error(&format!("options must be letters of {REGEX_OPTIONS:?}")))
+}
+
+/// decimal integers only, no sign prefix other than `-`
+fn parse_integer<T: std::str::FromStr>(value: &str) -> Option<T> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn parse_double(value: &str) -> Option<f64> {
+    match value {
+        "Infinity" => Some(f64::INFINITY),
+        "-Infinity" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ if value.bytes().any(|b| b.is_ascii_digit())
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) =>
+        {
+            value.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+/// documents with `$ref` and `$id` are DBRefs, their `$ref` and `$db` fields must be strings
+fn check_dbref(fields: &[(String, Json)], path: &str) -> Result<(), BsonError> {
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    };
+    if field("$ref").is_none() || field("$id").is_none() {
+        return Ok(());
+    }
+    for name in ["$ref", "$db"] {
+        match field(name) {
+            None | Some(Json::String(_)) => {}
+            Some(other) => {
+                return Err(invalid(
+                    path,
+                    &format!("DBRef: `{name}` must be a string, found {}", other.kind()),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn json_element(json: Json, path: &str) -> Result<Element, BsonError> {
+    Ok(match json {
+        Json::Null => Element::Null,
+        Json::Bool(value) => Element::Boolean(value),
+        Json::String(value) => Element::String(value),
+        Json::Number(number) if !number.contains(['.', 'e', 'E']) => {
+            if let Some(value) = parse_integer::<i32>(&number) {
+                Element::Int32(value)
+            } else if let Some(value) = parse_integer::<i64>(&number) {
+                Element::Int64(value)
+            } else {
+                Element::Double(
+                    number
+                        .parse()
+                        .map_err(|_| invalid(path, "invalid number"))?,
+                )
+            }
+        }
+        Json::Number(number) => Element::Double(
+            number
+                .parse()
+                .map_err(|_| invalid(path, "invalid number"))?,
+        ),
+        Json::Array(values) => {
+            let mut builder = ArrayBuilder::new();
+            for (index, value) in values.into_iter().enumerate() {
+                builder.append(&json_element(value, &child_path(path, &index.to_string()))?);
+            }
+            Element::ArrayDocument(builder.build())
+        }
+        Json::Object(fields) => match wrapper_name(&fields) {
+            Some(name) => wrapped_element(name, fields, path)?,
+            None => {
+                check_dbref(&fields, path)?;
+                Element::EmbededDocument(json_document(fields, path)?)
+            }
+        },
+    })
+}
+
+fn json_document(fields: Vec<(String, Json)>, path: &str) -> Result<Document, BsonError> {
+    let mut builder = DocumentBuilder::new();
+    for (key, value) in fields {
+        let path = child_path(path, &key);
+        if key.contains('\0') {
+            return Err(invalid(&path, "keys must not contain null bytes"));
+        }
+        builder.append(&key, &json_element(value, &path)?);
+    }
+    Ok(builder.build())
+}
+
+fn read_json(json: &str) -> Result<Json, BsonError> {
+    let mut reader = JsonReader {
+        input: json,
+        pos: 0,
+        depth: 0,
+    };
+    let value = reader.value()?;
+    reader.skip_whitespace();
+    if reader.pos != json.len() {
+        return Err(reader.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+impl Element {
+    /// parses a single Extended JSON value, canonical and relaxed forms are both accepted
+    pub fn from_extended_json(json: &str) -> Result<Element, BsonError> {
+        json_element(read_json(json)?, "")
+    }
+}
+
+impl Document {
+    /// parses an Extended JSON object, canonical and relaxed forms are both accepted
+    ///
+    /// ```rust
+    /// use bson2::{doc, Document};
+    ///
+    /// let doc = Document::from_extended_json(
+    ///     r#"{"int": {"$numberInt": "1"}, "long": {"$numberLong": "2"}, "list": [1.5]}"#,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(doc! { "int": 1, "long": 2i64, "list": [1.5] }, doc);
+    /// ```
+    pub fn from_extended_json(json: &str) -> Result<Document, BsonError> {
+        match Element::from_extended_json(json)? {
+            Element::EmbededDocument(doc) => Ok(doc),
+            _ => Err(BsonError::extended_json(
+                "expected a json object without type wrapper".to_string(),
+            )),
+        }
+    }
+}
+
+impl Array {
+    pub fn from_extended_json(json: &str) -> Result<Array, BsonError> {
+        match read_json(json)? {
+            Json::Array(values) => json_element(Json::Array(values), "")?.as_array(),
+            other => Err(BsonError::extended_json(format!(
+                "expected a json array, found {}",
+                other.kind()
+            ))),
+        }
+    }
+}
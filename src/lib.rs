@@ -1,24 +1,64 @@
-/// Parses Bson from byte array
-///
-///
-/// ```rust
-/// use bson2::Document;
-///
-/// let value: &[u8] = &[5, 0, 0, 0, 0];
-/// let doc = Document::try_from(value);
-/// assert_eq!(Ok(Document { data: [].to_vec() }), doc);
-///
-/// let value: &[u8] = &[16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0];
-/// let doc = Document::try_from(value).unwrap();
-/// let string = doc.get_string("hi").unwrap();
-/// assert_eq!("hi", string);
-/// for (name, value) in doc.iter(){
-///     println!("key is {name:?} and value is {value:?}")
-/// }
-/// ```
+//! Parses Bson from byte array
+//!
+//! ```rust
+//! use bson2::Document;
+//!
+//! let value: &[u8] = &[5, 0, 0, 0, 0];
+//! let doc = Document::try_from(value);
+//! assert_eq!(Ok(Document { data: [].to_vec() }), doc);
+//!
+//! let value: &[u8] = &[16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0];
+//! let doc = Document::try_from(value).unwrap();
+//! let string = doc.get_string("hi").unwrap();
+//! assert_eq!("hi", string);
+//! for (name, value) in doc.iter(){
+//!     println!("key is {name:?} and value is {value:?}")
+//! }
+//! assert_eq!(value, doc.to_bytes());
+//! ```
+
+#[macro_use]
+mod macros;
+pub mod borrowed;
+pub mod builder;
+pub mod column;
+pub mod datetime;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod decimal128;
+pub mod edit;
 pub mod element;
+pub mod encode;
+pub mod error;
+pub mod extjson;
+pub mod numeric;
 pub mod parse;
+pub mod regular_expression;
+#[cfg(feature = "serde")]
+pub mod ser;
+pub mod timeseries;
+pub mod timestamp;
+pub mod uuid;
+pub mod validate;
+pub mod vector;
 
+pub use borrowed::*;
+pub use builder::*;
+pub use column::ColumnIter;
+pub use datetime::*;
+#[cfg(feature = "serde")]
+pub use de::{from_document, from_element, from_slice};
+pub use decimal128::*;
+pub use edit::*;
 pub use element::*;
+pub use error::*;
+pub use extjson::ExtendedJsonMode;
+pub use regular_expression::Regex;
+#[cfg(feature = "serde")]
+pub use ser::{to_document, to_element};
+pub use timestamp::*;
+pub use uuid::*;
+pub use validate::MAX_NESTING_DEPTH;
+pub use vector::*;
 #[cfg(test)]
 mod test;
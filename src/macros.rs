@@ -0,0 +1,90 @@
+/// Builds a [`Document`](crate::Document) from a JSON like literal, panics on a key containing a
+/// null byte
+///
+/// ```rust
+/// use bson2::doc;
+///
+/// let doc = doc! {
+///     "bool": true,
+///     "array": [1, true, 1.0, { "test": "test" }],
+///     "dict": { "hi": "hi" },
+///     "nothing": null,
+/// };
+/// assert_eq!(Ok(true), doc.get_bool("bool"));
+/// assert_eq!(Ok(1.0), doc.get_array("array").unwrap().get_float(2));
+/// assert_eq!(Ok("hi".to_string()), doc.get_document("dict").unwrap().get_string("hi"));
+/// ```
+#[macro_export]
+macro_rules! doc {
+    () => {
+        $crate::DocumentBuilder::new().build()
+    };
+    (@fields $builder:ident) => {};
+    (@fields $builder:ident $key:tt : $($rest:tt)+) => {
+        $crate::doc!(@value $builder $key () $($rest)+)
+    };
+    (@value $builder:ident $key:tt ($($value:tt)+) , $($rest:tt)*) => {
+        $builder.append($key, &$crate::bson!($($value)+));
+        $crate::doc!(@fields $builder $($rest)*);
+    };
+    (@value $builder:ident $key:tt ($($value:tt)+)) => {
+        $builder.append($key, &$crate::bson!($($value)+));
+    };
+    (@value $builder:ident $key:tt ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::doc!(@value $builder $key ($($value)* $next) $($rest)*)
+    };
+    ($($tt:tt)+) => {{
+        let mut builder = $crate::DocumentBuilder::new();
+        $crate::doc!(@fields builder $($tt)+);
+        builder.build()
+    }};
+}
+
+/// Builds an [`Array`](crate::Array) from a JSON like literal
+///
+/// ```rust
+/// use bson2::array;
+///
+/// let array = array![1, "two", [3], { "four": 4 }];
+/// assert_eq!(Ok("two".to_string()), array.get_string(1));
+/// assert_eq!(Ok(4), array.get_document(3).unwrap().get_int32("four"));
+/// ```
+#[macro_export]
+macro_rules! array {
+    () => {
+        $crate::ArrayBuilder::new().build()
+    };
+    (@values $builder:ident ($($value:tt)+) , $($rest:tt)*) => {
+        $builder.append(&$crate::bson!($($value)+));
+        $crate::array!(@values $builder () $($rest)*);
+    };
+    (@values $builder:ident ($($value:tt)+)) => {
+        $builder.append(&$crate::bson!($($value)+));
+    };
+    (@values $builder:ident ()) => {};
+    (@values $builder:ident ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::array!(@values $builder ($($value)* $next) $($rest)*)
+    };
+    ($($tt:tt)+) => {{
+        let mut builder = $crate::ArrayBuilder::new();
+        $crate::array!(@values builder () $($tt)+);
+        builder.build()
+    }};
+}
+
+/// Converts a literal, nested `{...}`/`[...]` or any expression into an [`Element`](crate::Element)
+#[macro_export]
+macro_rules! bson {
+    (null) => {
+        $crate::Element::Null
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::Element::EmbededDocument($crate::doc! { $($tt)* })
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::Element::ArrayDocument($crate::array![ $($tt)* ])
+    };
+    ($value:expr) => {
+        $crate::Element::from($value)
+    };
+}
@@ -0,0 +1,175 @@
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+
+/// 2^63 as a double, the first value past `i64::MAX`
+const I64_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+fn overflow(expected: u8) -> BsonError {
+    BsonError::new(ErrorKind::NumericOverflow { expected })
+}
+
+fn precision_loss(expected: u8) -> BsonError {
+    BsonError::new(ErrorKind::PrecisionLoss { expected })
+}
+
+fn f64_to_i64(value: f64) -> Result<i64, BsonError> {
+    if value.is_nan() {
+        Err(precision_loss(ELEMENT_TYPE_INT64))
+    } else if !(-I64_LIMIT..I64_LIMIT).contains(&value) {
+        Err(overflow(ELEMENT_TYPE_INT64))
+    } else if value.fract() != 0.0 {
+        Err(precision_loss(ELEMENT_TYPE_INT64))
+    } else {
+        Ok(value as i64)
+    }
+}
+
+/// classified on the decimal itself, doubles can not tell fractions apart above 2^53
+fn decimal_to_i64(value: Decimal128) -> Result<i64, BsonError> {
+    if value.is_nan() {
+        return Err(precision_loss(ELEMENT_TYPE_INT64));
+    }
+    if value.is_infinite() {
+        return Err(overflow(ELEMENT_TYPE_INT64));
+    }
+    let (exponent, significand) = value.parts();
+    // integral part and whether a fraction remains, `None` when the integral part is beyond u128
+    let (integer, fraction) = if exponent >= 0 {
+        let integer = 10u128
+            .checked_pow(exponent as u32)
+            .and_then(|scale| significand.checked_mul(scale));
+        (integer, false)
+    } else {
+        match 10u128.checked_pow(exponent.unsigned_abs()) {
+            Some(scale) => (Some(significand / scale), significand % scale != 0),
+            // coefficients have at most 34 digits
+            None => (Some(0), significand != 0),
+        }
+    };
+    let limit = if value.is_negative() {
+        1u128 << 63
+    } else {
+        i64::MAX as u128
+    };
+    match integer {
+        Some(integer) if integer < limit || (integer == limit && !fraction) => {
+            if fraction {
+                Err(precision_loss(ELEMENT_TYPE_INT64))
+            } else if value.is_negative() {
+                Ok((integer as i128).wrapping_neg() as i64)
+            } else {
+                Ok(integer as i64)
+            }
+        }
+        _ => Err(overflow(ELEMENT_TYPE_INT64)),
+    }
+}
+
+impl Element {
+    /// Int32, Int64, Double or Decimal128 as an `i64`, when the value is an integer in range
+    ///
+    /// Fractions and NaN are [`ErrorKind::PrecisionLoss`], values past the `i64` range
+    /// [`ErrorKind::NumericOverflow`] and other types [`ErrorKind::TypeMismatch`].
+    ///
+    /// ```rust
+    /// use bson2::{doc, Decimal128, ErrorKind};
+    ///
+    /// let doc = doc! { "int": 3, "long": 3i64, "double": 3.0, "half": 2.5, "big": 1e19 };
+    /// assert_eq!(Ok(3), doc.get_i64_coerced("double"));
+    /// assert_eq!(Ok(3), doc.get_i32_checked("long"));
+    /// assert_eq!(Ok(3.0), doc.get_f64_coerced("int"));
+    /// let error = doc.get_i64_coerced("half").unwrap_err();
+    /// assert_eq!(ErrorKind::PrecisionLoss { expected: 0x12 }, error.kind);
+    /// let error = doc.get_i64_coerced("big").unwrap_err();
+    /// assert_eq!(ErrorKind::NumericOverflow { expected: 0x12 }, error.kind);
+    /// ```
+    pub fn as_i64_coerced(self) -> Result<i64, BsonError> {
+        let element_type = self.element_type();
+        match self {
+            Element::Int32(value) => Ok(value as i64),
+            Element::Int64(value) => Ok(value),
+            Element::Double(value) => f64_to_i64(value),
+            Element::Decimal(value) => decimal_to_i64(value),
+            _ => Err(BsonError::type_mismatch(ELEMENT_TYPE_INT64, element_type)),
+        }
+        .map_err(|error| error.with_element_type(element_type))
+    }
+
+    /// like [`Element::as_i64_coerced`], also failing with overflow outside the `i32` range
+    pub fn as_i32_checked(self) -> Result<i32, BsonError> {
+        let element_type = self.element_type();
+        self.as_i64_coerced()
+            .map_err(|error| match error.kind {
+                ErrorKind::NumericOverflow { .. } => overflow(ELEMENT_TYPE_INT32),
+                ErrorKind::PrecisionLoss { .. } => precision_loss(ELEMENT_TYPE_INT32),
+                _ => BsonError::type_mismatch(ELEMENT_TYPE_INT32, element_type),
+            })
+            .and_then(|value| i32::try_from(value).map_err(|_| overflow(ELEMENT_TYPE_INT32)))
+            .map_err(|error| error.with_element_type(element_type))
+    }
+
+    /// Int32, Int64, Double or Decimal128 as an `f64`, when the double holds the value exactly
+    ///
+    /// Int64 values above 2^53 may not fit, decimals like `0.1` do as they round trip through
+    /// their shortest form. Decimals beyond the double range are an overflow.
+    pub fn as_f64_coerced(self) -> Result<f64, BsonError> {
+        let element_type = self.element_type();
+        match self {
+            Element::Double(value) => Ok(value),
+            Element::Int32(value) => Ok(value as f64),
+            Element::Int64(value) if value as f64 as i128 == value as i128 => Ok(value as f64),
+            Element::Int64(_) => Err(precision_loss(ELEMENT_TYPE_DOUBLE)),
+            Element::Decimal(value) => f64::try_from(value).map_err(|_| {
+                if value.to_f64().is_infinite() {
+                    overflow(ELEMENT_TYPE_DOUBLE)
+                } else {
+                    precision_loss(ELEMENT_TYPE_DOUBLE)
+                }
+            }),
+            _ => Err(BsonError::type_mismatch(ELEMENT_TYPE_DOUBLE, element_type)),
+        }
+        .map_err(|error| error.with_element_type(element_type))
+    }
+}
+
+impl Document {
+    pub fn get_i64_coerced(&self, key: &str) -> Result<i64, BsonError> {
+        self.get_any(key)?
+            .as_i64_coerced()
+            .map_err(|error| error.with_key(key))
+    }
+
+    pub fn get_i32_checked(&self, key: &str) -> Result<i32, BsonError> {
+        self.get_any(key)?
+            .as_i32_checked()
+            .map_err(|error| error.with_key(key))
+    }
+
+    pub fn get_f64_coerced(&self, key: &str) -> Result<f64, BsonError> {
+        self.get_any(key)?
+            .as_f64_coerced()
+            .map_err(|error| error.with_key(key))
+    }
+}
+
+impl Array {
+    pub fn get_i64_coerced(&self, key: usize) -> Result<i64, BsonError> {
+        self.get_any(key)?
+            .as_i64_coerced()
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+
+    pub fn get_i32_checked(&self, key: usize) -> Result<i32, BsonError> {
+        self.get_any(key)?
+            .as_i32_checked()
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+
+    pub fn get_f64_coerced(&self, key: usize) -> Result<f64, BsonError> {
+        self.get_any(key)?
+            .as_f64_coerced()
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+}
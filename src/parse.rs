@@ -3,35 +3,119 @@ use nom::{
     error::ParseError,
     number::complete::{be_u8, le_f64, le_i32, le_i64, le_u64},
     sequence::tuple,
-    IResult,
 };
 
+use super::borrowed::*;
 use super::element::*;
+use super::error::*;
+use super::regular_expression::*;
 
-const ELEMENT_TYPE_DOUBLE: u8 = 0x01;
-const ELEMENT_TYPE_STRING: u8 = 0x02;
-const ELEMENT_TYPE_EMBED_DOCUMENT: u8 = 0x03;
-const ELEMENT_TYPE_ARRAY_DOCUMENT: u8 = 0x04;
-const ELEMENT_TYPE_BINARY: u8 = 0x05;
-const ELEMENT_TYPE_UNDEFINED: u8 = 0x06;
-const ELEMENT_TYPE_OBJECT_ID: u8 = 0x07;
-const ELEMENT_TYPE_BOOLEAN: u8 = 0x08;
-const ELEMENT_TYPE_DATETIME: u8 = 0x09;
-const ELEMENT_TYPE_NULL: u8 = 0x0A;
-const ELEMENT_TYPE_CSTRING: u8 = 0x0B;
-const ELEMENT_TYPE_DBPOINTER: u8 = 0x0C;
-const ELEMENT_TYPE_JAVASCRIPTCODE: u8 = 0x0D;
-const ELEMENT_TYPE_SYMBOL: u8 = 0x0E;
-const ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE: u8 = 0x0F;
-const ELEMENT_TYPE_INT32: u8 = 0x10;
-const ELEMENT_TYPE_TIMESTAMP: u8 = 0x11;
-const ELEMENT_TYPE_INT64: u8 = 0x12;
-const ELEMENT_TYPE_DECIMAL128: u8 = 0x13;
-const ELEMENT_TYPE_MIN: u8 = 0xFF;
-const ELEMENT_TYPE_MAX: u8 = 0x7F;
+pub(crate) const ELEMENT_TYPE_DOUBLE: u8 = 0x01;
+pub(crate) const ELEMENT_TYPE_STRING: u8 = 0x02;
+pub(crate) const ELEMENT_TYPE_EMBED_DOCUMENT: u8 = 0x03;
+pub(crate) const ELEMENT_TYPE_ARRAY_DOCUMENT: u8 = 0x04;
+pub(crate) const ELEMENT_TYPE_BINARY: u8 = 0x05;
+pub(crate) const ELEMENT_TYPE_UNDEFINED: u8 = 0x06;
+pub(crate) const ELEMENT_TYPE_OBJECT_ID: u8 = 0x07;
+pub(crate) const ELEMENT_TYPE_BOOLEAN: u8 = 0x08;
+pub(crate) const ELEMENT_TYPE_DATETIME: u8 = 0x09;
+pub(crate) const ELEMENT_TYPE_NULL: u8 = 0x0A;
+pub(crate) const ELEMENT_TYPE_REGULAR_EXPRESSION: u8 = 0x0B;
+pub(crate) const ELEMENT_TYPE_DBPOINTER: u8 = 0x0C;
+pub(crate) const ELEMENT_TYPE_JAVASCRIPTCODE: u8 = 0x0D;
+pub(crate) const ELEMENT_TYPE_SYMBOL: u8 = 0x0E;
+pub(crate) const ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE: u8 = 0x0F;
+pub(crate) const ELEMENT_TYPE_INT32: u8 = 0x10;
+pub(crate) const ELEMENT_TYPE_TIMESTAMP: u8 = 0x11;
+pub(crate) const ELEMENT_TYPE_INT64: u8 = 0x12;
+pub(crate) const ELEMENT_TYPE_DECIMAL128: u8 = 0x13;
+pub(crate) const ELEMENT_TYPE_MIN: u8 = 0xFF;
+pub(crate) const ELEMENT_TYPE_MAX: u8 = 0x7F;
 const NULL_BYTE: &str = "\x00";
 
-pub(crate) fn parse_decimal_128(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+/// nom error keeping the input where decoding stopped and why
+#[derive(Debug)]
+pub struct ParseFailure<'a> {
+    pub input: &'a [u8],
+    pub kind: ErrorKind,
+}
+
+impl<'a> ParseError<&'a [u8]> for ParseFailure<'a> {
+    fn from_error_kind(input: &'a [u8], kind: nom::error::ErrorKind) -> Self {
+        let kind = match kind {
+            nom::error::ErrorKind::Eof | nom::error::ErrorKind::TakeUntil => ErrorKind::Truncated,
+            _ => ErrorKind::Malformed,
+        };
+        ParseFailure { input, kind }
+    }
+
+    fn append(_input: &'a [u8], _kind: nom::error::ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+pub type ParseResult<'a, T> = nom::IResult<&'a [u8], T, ParseFailure<'a>>;
+
+fn failure(input: &[u8], kind: ErrorKind) -> nom::Err<ParseFailure<'_>> {
+    nom::Err::Error(ParseFailure { input, kind })
+}
+
+/// converts a nom failure into a [`BsonError`], its offset counted from the start of `base`
+/// plus `header` bytes not part of `base`
+pub(crate) fn parse_error(base: &[u8], err: nom::Err<ParseFailure>, header: usize) -> BsonError {
+    match err {
+        nom::Err::Error(failure) | nom::Err::Failure(failure) => {
+            let offset = (failure.input.as_ptr() as usize)
+                .wrapping_sub(base.as_ptr() as usize)
+                .min(base.len());
+            BsonError::new(failure.kind).with_offset(offset + header)
+        }
+        nom::Err::Incomplete(_) => BsonError::new(ErrorKind::Truncated),
+    }
+}
+
+/// parses the element at `input`, a suffix of the document body `data`, failures carry the
+/// offset, key and element type
+pub(crate) fn decode_element<'a>(
+    data: &'a [u8],
+    input: &'a [u8],
+) -> Result<(&'a [u8], KeyPairRef<'a>), BsonError> {
+    parse_any_ref(input).map_err(|err| {
+        let mut error = parse_error(data, err, 4);
+        if let Some(element_type) = input.first() {
+            error = error.with_element_type(*element_type);
+        }
+        if let Some(Ok((_, key))) = input.get(1..).map(parse_estring) {
+            error = error.with_key(key);
+        }
+        error
+    })
+}
+
+/// int32 length prefix, values below `min` are rejected
+fn parse_length(input: &[u8], min: i32) -> ParseResult<'_, usize> {
+    let (rest, size) = le_i32(input)?;
+    if size < min {
+        return Err(failure(input, ErrorKind::BadLength(size)));
+    }
+    Ok((rest, size as usize))
+}
+
+/// length of a string including its trailing NUL
+fn parse_string_length(input: &[u8]) -> ParseResult<'_, usize> {
+    parse_length(input, 1)
+}
+
+/// length of a document including the length itself and the trailing NUL
+pub(crate) fn parse_document_length(input: &[u8]) -> ParseResult<'_, usize> {
+    parse_length(input, 5)
+}
+
+fn parse_binary_length(input: &[u8]) -> ParseResult<'_, usize> {
+    parse_length(input, 0)
+}
+
+pub(crate) fn parse_decimal_128<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, decimal)) = tuple((
         tag(&[ELEMENT_TYPE_DECIMAL128]),
         parse_estring,
@@ -55,239 +139,270 @@ pub(crate) fn parse_decimal_128(input: &[u8]) -> IResult<&[u8], KeyPair<Element>
         decimal[14],
         decimal[15],
     ];
-    Ok((input, (ename, Element::Decimal(decimal))))
+    Ok((input, (ename, ElementRef::Decimal(decimal.into()))))
 }
 
-pub(crate) fn parse_double(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_double<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, double)) =
         tuple((tag(&[ELEMENT_TYPE_DOUBLE]), parse_estring, le_f64))(input)?;
-    Ok((input, (ename, Element::Double(double))))
+    Ok((input, (ename, ElementRef::Double(double))))
 }
 
-pub(crate) fn parse_int32(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_int32<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, int32)) =
         tuple((tag(&[ELEMENT_TYPE_INT32]), parse_estring, le_i32))(input)?;
-    Ok((input, (ename, Element::Int32(int32))))
+    Ok((input, (ename, ElementRef::Int32(int32))))
 }
 
-pub(crate) fn parse_int64(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_int64<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, int64)) =
         tuple((tag(&[ELEMENT_TYPE_INT64]), parse_estring, le_i64))(input)?;
-    Ok((input, (ename, Element::Int64(int64))))
+    Ok((input, (ename, ElementRef::Int64(int64))))
 }
 
-pub(crate) fn parse_uint64(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_datetime<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, millis)) =
+        tuple((tag(&[ELEMENT_TYPE_DATETIME]), parse_estring, le_i64))(input)?;
+    Ok((input, (ename, ElementRef::DateTime(millis.into()))))
+}
+
+pub(crate) fn parse_uint64<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, uint64)) =
         tuple((tag(&[ELEMENT_TYPE_TIMESTAMP]), parse_estring, le_u64))(input)?;
-    Ok((input, (ename, Element::Timestamp(uint64))))
+    Ok((input, (ename, ElementRef::Timestamp(uint64.into()))))
 }
 
-pub(crate) fn parse_null(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_null<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename)) = tuple((tag(&[ELEMENT_TYPE_NULL]), parse_estring))(input)?;
-    Ok((input, (ename, Element::Null)))
+    Ok((input, (ename, ElementRef::Null)))
 }
 
-pub(crate) fn parse_symbol(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, size)) =
-        tuple((tag(&[ELEMENT_TYPE_SYMBOL]), parse_estring, le_i32))(input)?;
-    let (input, string) = take(size as usize - 1)(input)?;
-    let (input, _) = be_u8(input)?;
-    let string = map_utf8_error(input, string)?;
-    Ok((input, (ename, Element::Symbol(string))))
-}
-
-pub(crate) fn parse_string(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, size)) =
-        tuple((tag(&[ELEMENT_TYPE_STRING]), parse_estring, le_i32))(input)?;
-    let (input, string) = take(size as usize - 1)(input)?;
+pub(crate) fn parse_symbol<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
+        tag(&[ELEMENT_TYPE_SYMBOL]),
+        parse_estring,
+        parse_string_length,
+    ))(input)?;
+    let (input, string) = take(size - 1)(input)?;
     let (input, _) = be_u8(input)?;
-    let string = map_utf8_error(input, string)?;
-    Ok((input, (ename, Element::String(string))))
+    let string = map_utf8_error(string)?;
+    Ok((input, (ename, ElementRef::Symbol(string))))
 }
 
-pub(crate) fn parse_javascript(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, _size)) =
-        tuple((tag(&[ELEMENT_TYPE_JAVASCRIPTCODE]), parse_estring, le_i32))(input)?;
-    let (input, string) = take(_size as usize)(input)?;
+pub(crate) fn parse_string<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
+        tag(&[ELEMENT_TYPE_STRING]),
+        parse_estring,
+        parse_string_length,
+    ))(input)?;
+    let (input, string) = take(size - 1)(input)?;
     let (input, _) = be_u8(input)?;
-    let string = map_utf8_error(input, string)?;
-    Ok((input, (ename, Element::Javascript(string))))
+    let string = map_utf8_error(string)?;
+    Ok((input, (ename, ElementRef::String(string))))
 }
 
-pub(crate) fn parse_javascript_with_scope(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, total_size, string_size)) = tuple((
+pub(crate) fn parse_javascript<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
         tag(&[ELEMENT_TYPE_JAVASCRIPTCODE]),
         parse_estring,
-        le_i32,
-        le_i32,
+        parse_string_length,
     ))(input)?;
-
-    let (input, string) = take(string_size as usize)(input)?;
-    let (input, _null_byte) = be_u8(input)?;
-    let (input, sub_document) = take((total_size - string_size - 1) as usize)(input)?;
-    let (_, embed_document_size) = le_i32(sub_document)?;
-    let (_, document) = take((embed_document_size - 5) as usize)(sub_document)?;
+    let (input, string) = take(size - 1)(input)?;
     let (input, _) = be_u8(input)?;
-    let string = map_utf8_error(input, string)?;
+    let string = map_utf8_error(string)?;
+    Ok((input, (ename, ElementRef::Javascript(string))))
+}
+
+pub(crate) fn parse_javascript_with_scope<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename)) =
+        tuple((tag(&[ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE]), parse_estring))(input)?;
+    // the total length counts itself, the code string and the scope, which must fill it exactly
+    let (_, total_size) = parse_length(input, 14)?;
+    let (input, field) = take(total_size)(input)?;
+
+    let (rest, code_size) = parse_string_length(&field[4..])?;
+    let (rest, code) = take(code_size - 1)(rest)?;
+    let (rest, terminator) = take(1usize)(rest)?;
+    if terminator != [0] {
+        return Err(failure(terminator, ErrorKind::Malformed));
+    }
+    let code = map_utf8_error(code)?;
+
+    let (_, scope_size) = parse_document_length(rest)?;
+    if scope_size != rest.len() {
+        return Err(failure(field, ErrorKind::BadLength(total_size as i32)));
+    }
+    if rest[scope_size - 1] != 0 {
+        return Err(failure(&rest[scope_size - 1..], ErrorKind::Malformed));
+    }
+    let scope = DocumentRef {
+        data: &rest[4..scope_size - 1],
+    };
     Ok((
         input,
         (
             ename,
-            Element::JavascriptCode(
-                string,
-                Document {
-                    data: document.to_vec(),
-                },
-            ),
+            ElementRef::JavascriptCode(CodeWithScopeRef { code, scope }),
         ),
     ))
 }
 
-pub(crate) fn parse_object_id(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_object_id<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, ar)) =
         tuple((tag(&[ELEMENT_TYPE_OBJECT_ID]), parse_estring, take(12usize)))(input)?;
     let ob = [
         ar[0], ar[1], ar[2], ar[3], ar[4], ar[5], ar[6], ar[7], ar[8], ar[9], ar[10], ar[11],
     ]
     .into();
-    Ok((input, (ename, Element::ObjectId(ob))))
+    Ok((input, (ename, ElementRef::ObjectId(ob))))
 }
 
-pub(crate) fn parse_boolean(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_boolean<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename, double)) =
         tuple((tag(&[ELEMENT_TYPE_BOOLEAN]), parse_estring, be_u8))(input)?;
-    Ok((input, (ename, Element::Boolean(double != 0))))
+    Ok((input, (ename, ElementRef::Boolean(double != 0))))
 }
 
-fn map_utf8_error<'a>(
-    input: &'a [u8],
-    ename: &'a [u8],
-) -> Result<String, nom::Err<nom::error::Error<&'a [u8]>>> {
-    match String::from_utf8(ename.to_vec()) {
-        Ok(ename) => Ok(ename),
-        Err(_) => Err(nom::Err::Error(nom::error::Error {
-            input,
-            code: nom::error::ErrorKind::Fail,
-        })),
-    }
+fn map_utf8_error(bytes: &[u8]) -> Result<&str, nom::Err<ParseFailure<'_>>> {
+    std::str::from_utf8(bytes).map_err(|_| failure(bytes, ErrorKind::InvalidUtf8))
 }
 
-pub(crate) fn parse_estring(input: &[u8]) -> IResult<&[u8], String> {
+pub(crate) fn parse_estring(input: &[u8]) -> ParseResult<'_, &str> {
     let (input, ename) = (take_until(NULL_BYTE))(input)?;
     let (input, _ignore_x00) = be_u8(input)?;
-    let ename = map_utf8_error(input, ename)?;
+    let ename = map_utf8_error(ename)?;
     Ok((input, ename))
 }
 
-pub(crate) fn parse_cstring(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, ename) = parse_estring(input)?;
-    let (input, cstring1) = (take_until(NULL_BYTE))(input)?;
-    let (input, _) = be_u8(input)?;
-    let cstring1 = map_utf8_error(input, cstring1)?;
-    let (input, cstring2) = (take_until(NULL_BYTE))(input)?;
-    let (input, _) = be_u8(input)?;
-    let cstring2 = map_utf8_error(input, cstring2)?;
-    Ok((input, (ename, Element::Cstring(cstring1, cstring2))))
+pub(crate) fn parse_regular_expression<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, pattern, options)) = tuple((
+        tag(&[ELEMENT_TYPE_REGULAR_EXPRESSION]),
+        parse_estring,
+        parse_estring,
+        parse_estring,
+    ))(input)?;
+    if !valid_options(options) {
+        return Err(failure(options.as_bytes(), ErrorKind::Malformed));
+    }
+    Ok((
+        input,
+        (ename, ElementRef::RegularExpression { pattern, options }),
+    ))
 }
 
-pub(crate) fn parse_dbpointer(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, arr)) =
-        tuple((tag(&[ELEMENT_TYPE_DBPOINTER]), parse_estring, take(12usize)))(input)?;
-    let db_pointer = [
-        arr[0], arr[1], arr[2], arr[3], arr[4], arr[5], arr[6], arr[7], arr[8], arr[9], arr[10],
-        arr[11],
-    ];
-    Ok((input, (ename, Element::ObjectId(db_pointer.into()))))
+pub(crate) fn parse_dbpointer<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
+        tag(&[ELEMENT_TYPE_DBPOINTER]),
+        parse_estring,
+        parse_string_length,
+    ))(input)?;
+    let (input, namespace) = take(size - 1)(input)?;
+    let (input, terminator) = take(1usize)(input)?;
+    if terminator != [0] {
+        return Err(failure(terminator, ErrorKind::Malformed));
+    }
+    let namespace = map_utf8_error(namespace)?;
+    let (input, ar) = take(12usize)(input)?;
+    let id = [
+        ar[0], ar[1], ar[2], ar[3], ar[4], ar[5], ar[6], ar[7], ar[8], ar[9], ar[10], ar[11],
+    ]
+    .into();
+    Ok((
+        input,
+        (ename, ElementRef::DbPointer(DbPointerRef { namespace, id })),
+    ))
 }
 
-pub(crate) fn parse_embeded_document(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, _size)) =
-        tuple((tag(&[ELEMENT_TYPE_EMBED_DOCUMENT]), parse_estring, le_i32))(input)?;
-    let (input, next_doc) = take(_size as usize - 5)(input)?;
+pub(crate) fn parse_embeded_document<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
+        tag(&[ELEMENT_TYPE_EMBED_DOCUMENT]),
+        parse_estring,
+        parse_document_length,
+    ))(input)?;
+    let (input, next_doc) = take(size - 5)(input)?;
     let (input, _) = be_u8(input)?;
     Ok((
         input,
         (
             ename,
-            Element::EmbededDocument(Document {
-                data: next_doc.to_vec(),
-            }),
+            ElementRef::EmbededDocument(DocumentRef { data: next_doc }),
         ),
     ))
 }
 
-pub(crate) fn parse_document(input: &[u8]) -> IResult<&[u8], Document> {
-    let (input, size) = le_i32(input)?;
-    let (input, next_doc) = take(size as usize - 5)(input)?;
-    Ok((
-        input,
-        Document {
-            data: next_doc.to_vec(),
-        },
-    ))
+pub(crate) fn parse_document(input: &[u8]) -> ParseResult<'_, Document> {
+    let (input, doc) = parse_document_ref(input)?;
+    Ok((input, doc.to_owned()))
 }
 
-pub(crate) fn parse_array_document(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, _size)) =
-        tuple((tag(&[ELEMENT_TYPE_ARRAY_DOCUMENT]), parse_estring, le_i32))(input)?;
-    let (input, next_doc) = take(_size as usize - 5)(input)?;
+pub(crate) fn parse_document_ref(input: &[u8]) -> ParseResult<'_, DocumentRef<'_>> {
+    let (input, size) = parse_document_length(input)?;
+    let (input, next_doc) = take(size - 5)(input)?;
+    Ok((input, DocumentRef { data: next_doc }))
+}
+
+pub(crate) fn parse_array_document<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size)) = tuple((
+        tag(&[ELEMENT_TYPE_ARRAY_DOCUMENT]),
+        parse_estring,
+        parse_document_length,
+    ))(input)?;
+    let (input, next_doc) = take(size - 5)(input)?;
     let (input, _) = be_u8(input)?;
     Ok((
         input,
         (
             ename,
-            Element::ArrayDocument(Array {
-                data: next_doc.to_vec(),
-            }),
+            ElementRef::ArrayDocument(ArrayRef { data: next_doc }),
         ),
     ))
 }
 
-pub(crate) fn parse_min(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_min<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename)) = tuple((tag(&[ELEMENT_TYPE_MIN]), parse_estring))(input)?;
-    Ok((input, (ename, Element::Min)))
+    Ok((input, (ename, ElementRef::Min)))
 }
 
-pub(crate) fn parse_undefined(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_undefined<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename)) = tuple((tag(&[ELEMENT_TYPE_UNDEFINED]), parse_estring))(input)?;
-    Ok((input, (ename, Element::Min)))
+    Ok((input, (ename, ElementRef::Undefined)))
 }
 
-pub(crate) fn parse_max(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
+pub(crate) fn parse_max<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
     let (input, (_, ename)) = tuple((tag(&[ELEMENT_TYPE_MAX]), parse_estring))(input)?;
-    Ok((input, (ename, Element::Max)))
-}
-
-pub(crate) fn parse_binary(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    let (input, (_, ename, size, binary_type)) =
-        tuple((tag(&[ELEMENT_TYPE_BINARY]), parse_estring, le_i32, be_u8))(input)?;
-
-    let (input, byte_array) = take(size as usize)(input)?;
-
-    match BinaryType::try_from(binary_type) {
-        Ok(binary_type) => Ok((
-            input,
-            (
-                ename,
-                Element::Binary(Binary {
-                    binary_type,
-                    data: byte_array.to_vec(),
-                }),
-            ),
-        )),
-        Err(_err) => Err(nom::Err::Error(nom::error::Error {
-            input,
-            code: nom::error::ErrorKind::Fail,
-        })),
-    }
+    Ok((input, (ename, ElementRef::Max)))
+}
+
+pub(crate) fn parse_binary<'a>(input: &'a [u8]) -> ParseResult<'a, KeyPairRef<'a>> {
+    let (input, (_, ename, size, binary_type)) = tuple((
+        tag(&[ELEMENT_TYPE_BINARY]),
+        parse_estring,
+        parse_binary_length,
+        be_u8,
+    ))(input)?;
+
+    let (input, byte_array) = take(size)(input)?;
+    let binary_type = BinaryType::from(binary_type);
+    let data = if binary_type == BinaryType::BinaryBinary {
+        // the old binary subtype starts with the length of the rest once more
+        let (data, inner_size) = le_i32(byte_array)?;
+        if inner_size < 0 || inner_size as usize != data.len() {
+            return Err(failure(byte_array, ErrorKind::BadLength(inner_size)));
+        }
+        data
+    } else {
+        byte_array
+    };
+    Ok((
+        input,
+        (ename, ElementRef::Binary(BinaryRef { binary_type, data })),
+    ))
 }
 
-pub fn parse_any(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
-    if input.len() == 0 {
-        return Err(nom::Err::Error(nom::error::Error::from_error_kind(
-            input,
-            nom::error::ErrorKind::Fail,
-        )));
+/// parses one element without copying, keys and values borrow from `input`
+pub fn parse_any_ref(input: &[u8]) -> ParseResult<'_, KeyPairRef<'_>> {
+    if input.is_empty() {
+        return Err(failure(input, ErrorKind::Truncated));
     }
     let element_type = input[0];
 
@@ -300,9 +415,9 @@ pub fn parse_any(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
         ELEMENT_TYPE_UNDEFINED => parse_undefined,
         ELEMENT_TYPE_OBJECT_ID => parse_object_id,
         ELEMENT_TYPE_BOOLEAN => parse_boolean,
-        ELEMENT_TYPE_DATETIME => parse_int64,
+        ELEMENT_TYPE_DATETIME => parse_datetime,
         ELEMENT_TYPE_NULL => parse_null,
-        ELEMENT_TYPE_CSTRING => parse_cstring,
+        ELEMENT_TYPE_REGULAR_EXPRESSION => parse_regular_expression,
         ELEMENT_TYPE_DBPOINTER => parse_dbpointer,
         ELEMENT_TYPE_JAVASCRIPTCODE => parse_javascript,
         ELEMENT_TYPE_SYMBOL => parse_symbol,
@@ -313,12 +428,12 @@ pub fn parse_any(input: &[u8]) -> IResult<&[u8], KeyPair<Element>> {
         ELEMENT_TYPE_DECIMAL128 => parse_decimal_128,
         ELEMENT_TYPE_MIN => parse_min,
         ELEMENT_TYPE_MAX => parse_max,
-        _ => {
-            return Err(nom::Err::Error(nom::error::Error::from_error_kind(
-                input,
-                nom::error::ErrorKind::Fail,
-            )));
-        }
+        _ => return Err(failure(input, ErrorKind::UnknownType)),
     }(input)?;
     Ok((input, out))
 }
+
+pub fn parse_any(input: &[u8]) -> ParseResult<'_, KeyPair<Element>> {
+    let (input, (ename, element)) = parse_any_ref(input)?;
+    Ok((input, (ename.to_string(), element.to_owned())))
+}
@@ -0,0 +1,111 @@
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+
+/// option letters a regular expression may carry, in the order BSON stores them
+pub(crate) const REGEX_OPTIONS: &str = "imsux";
+
+pub(crate) fn valid_options(options: &str) -> bool {
+    options.chars().all(|c| REGEX_OPTIONS.contains(c))
+}
+
+/// options sorted alphabetically without duplicates, the form BSON requires
+pub(crate) fn sort_options(options: &str) -> String {
+    let mut letters: Vec<char> = options.chars().collect();
+    letters.sort_unstable();
+    letters.dedup();
+    letters.into_iter().collect()
+}
+
+/// BSON regular expression (0x0B), a pattern and its option letters
+///
+/// Options are the letters `i` (case insensitive), `m` (multiline), `s` (dot matches newline),
+/// `u` (unicode) and `x` (extended). [`Regex::new`] sorts them as the specification requires,
+/// decoded values keep the options as stored.
+///
+/// ```rust
+/// use bson2::{doc, Regex};
+///
+/// let doc = doc! { "name": Regex::new("^bs.n$", "xi").unwrap() };
+/// let regex = doc.get_regex("name").unwrap();
+/// assert_eq!("ix", regex.options);
+/// assert!(Regex::new("^bs.n$", "g").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Regex {
+    pub pattern: String,
+    pub options: String,
+}
+
+impl Regex {
+    /// checks the option letters and sorts them, patterns can not contain NUL
+    pub fn new(pattern: impl Into<String>, options: &str) -> Result<Regex, BsonError> {
+        let pattern = pattern.into();
+        if pattern.contains('\0') {
+            return Err(BsonError::custom_message(
+                "regular expression pattern contains a null byte".to_string(),
+            ));
+        }
+        if !valid_options(options) {
+            return Err(BsonError::custom_message(format!(
+                "invalid regular expression options {options:?}, expected letters of {REGEX_OPTIONS:?}"
+            )));
+        }
+        Ok(Regex {
+            pattern,
+            options: sort_options(options),
+        })
+    }
+
+    /// Compiles the pattern with the `regex` crate, options become inline flags
+    ///
+    /// `u` needs no flag as the `regex` crate is unicode aware by default. PCRE only syntax
+    /// such as backreferences and lookaround fails to compile.
+    #[cfg(feature = "regex")]
+    pub fn compile(&self) -> Result<regex::Regex, BsonError> {
+        let flags: String = self.options.chars().filter(|c| *c != 'u').collect();
+        let pattern = if flags.is_empty() {
+            self.pattern.clone()
+        } else {
+            format!("(?{flags}){}", self.pattern)
+        };
+        regex::Regex::new(&pattern).map_err(|error| BsonError::custom_message(error.to_string()))
+    }
+}
+
+impl From<Regex> for Element {
+    fn from(value: Regex) -> Self {
+        Element::RegularExpression {
+            pattern: value.pattern,
+            options: value.options,
+        }
+    }
+}
+
+impl Element {
+    pub fn as_regex(self) -> Result<Regex, BsonError> {
+        match self {
+            Element::RegularExpression { pattern, options } => Ok(Regex { pattern, options }),
+            other => Err(BsonError::type_mismatch(
+                ELEMENT_TYPE_REGULAR_EXPRESSION,
+                other.element_type(),
+            )),
+        }
+    }
+}
+
+impl Document {
+    pub fn get_regex(&self, key: &str) -> Result<Regex, BsonError> {
+        self.get_any(key)?
+            .as_regex()
+            .map_err(|error| error.with_key(key))
+    }
+}
+
+impl Array {
+    pub fn get_regex(&self, key: usize) -> Result<Regex, BsonError> {
+        self.get_any(key)?
+            .as_regex()
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+}
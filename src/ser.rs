@@ -0,0 +1,455 @@
+use serde::ser::{self, Serialize};
+
+use super::builder::*;
+use super::datetime::*;
+use super::decimal128::*;
+use super::element::*;
+use super::error::*;
+use super::timestamp::*;
+
+// newtype names used to carry bson specific types through serde untouched
+pub(crate) const OBJECT_ID_NEWTYPE: &str = "$__bson2_private_ObjectId";
+pub(crate) const BINARY_NEWTYPE: &str = "$__bson2_private_Binary";
+pub(crate) const DECIMAL128_NEWTYPE: &str = "$__bson2_private_Decimal128";
+pub(crate) const DATETIME_NEWTYPE: &str = "$__bson2_private_DateTime";
+pub(crate) const TIMESTAMP_NEWTYPE: &str = "$__bson2_private_Timestamp";
+
+/// Serializes `value` into a [`Document`], `value` has to serialize as a map or struct
+///
+/// ```rust
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+///     age: i32,
+/// }
+///
+/// let doc = bson2::to_document(&User { name: "hi".to_string(), age: 5 }).unwrap();
+/// assert_eq!(bson2::doc! { "name": "hi", "age": 5 }, doc);
+/// ```
+pub fn to_document<T: Serialize + ?Sized>(value: &T) -> Result<Document, BsonError> {
+    match to_element(value)? {
+        Element::EmbededDocument(doc) => Ok(doc),
+        _ => Err(BsonError::custom_message(
+            "value did not serialize to a document".to_string(),
+        )),
+    }
+}
+
+/// Serializes `value` into a single [`Element`]
+pub fn to_element<T: Serialize + ?Sized>(value: &T) -> Result<Element, BsonError> {
+    value.serialize(Serializer)
+}
+
+impl ser::Error for BsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        BsonError::custom_message(msg.to_string())
+    }
+}
+
+/// serializes raw bytes as bson binary instead of an array of integers
+pub(crate) struct Bytes<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+impl Serialize for ObjectId {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(OBJECT_ID_NEWTYPE, &self.to_string())
+    }
+}
+
+impl Serialize for Binary {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let subtype = u8::from(&self.binary_type);
+        serializer.serialize_newtype_struct(BINARY_NEWTYPE, &(subtype, Bytes(&self.data)))
+    }
+}
+
+impl Serialize for Decimal128 {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DECIMAL128_NEWTYPE, &Bytes(&self.bytes))
+    }
+}
+
+impl Serialize for DateTime {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DATETIME_NEWTYPE, &self.millis)
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(TIMESTAMP_NEWTYPE, &(u64::from(*self) as i64))
+    }
+}
+
+fn invalid(name: &str) -> BsonError {
+    BsonError::custom_message(format!("invalid {name} representation"))
+}
+
+/// turns the payload of one of the private newtypes back into its bson element
+fn special_element(name: &str, payload: Element) -> Result<Element, BsonError> {
+    match (name, payload) {
+        (OBJECT_ID_NEWTYPE, Element::String(hex)) => {
+            let mut id = [0u8; 12];
+            hex::decode_to_slice(hex, &mut id).map_err(|_| invalid("ObjectId"))?;
+            Ok(Element::ObjectId(id.into()))
+        }
+        (BINARY_NEWTYPE, Element::ArrayDocument(array)) => {
+            let subtype = array.get_int32(0).map_err(|_| invalid("Binary"))?;
+            let data = array.get_binary(1).map_err(|_| invalid("Binary"))?.data;
+            Ok(Element::Binary(Binary {
+                binary_type: BinaryType::from(subtype as u8),
+                data,
+            }))
+        }
+        (DECIMAL128_NEWTYPE, Element::Binary(binary)) => {
+            let bytes: [u8; 16] = binary.data.try_into().map_err(|_| invalid("Decimal128"))?;
+            Ok(Element::Decimal(bytes.into()))
+        }
+        (DATETIME_NEWTYPE, Element::Int64(millis)) => Ok(Element::DateTime(millis.into())),
+        (TIMESTAMP_NEWTYPE, Element::Int64(value)) => Ok(Element::Timestamp((value as u64).into())),
+        (name, _) => Err(invalid(name)),
+    }
+}
+
+/// Serde serializer producing [`Element`] values
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = VariantSerializer<ArraySerializer>;
+    type SerializeMap = DocumentSerializer;
+    type SerializeStruct = DocumentSerializer;
+    type SerializeStructVariant = VariantSerializer<DocumentSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<Element, BsonError> {
+        Ok(Element::Boolean(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Element, BsonError> {
+        Ok(Element::Int32(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Element, BsonError> {
+        Ok(Element::Int32(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Element, BsonError> {
+        Ok(Element::Int32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Element, BsonError> {
+        Ok(Element::Int64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Element, BsonError> {
+        Ok(Element::Int32(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Element, BsonError> {
+        Ok(Element::Int32(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Element, BsonError> {
+        Ok(match i32::try_from(v) {
+            Ok(v) => Element::Int32(v),
+            Err(_) => Element::Int64(v.into()),
+        })
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Element, BsonError> {
+        i64::try_from(v)
+            .map(Element::Int64)
+            .map_err(|_| BsonError::custom_message(format!("{v} does not fit into int64")))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Element, BsonError> {
+        Ok(Element::Double(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Element, BsonError> {
+        Ok(Element::Double(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Element, BsonError> {
+        Ok(Element::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Element, BsonError> {
+        Ok(Element::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Element, BsonError> {
+        Ok(Element::Binary(Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: v.to_vec(),
+        }))
+    }
+
+    fn serialize_none(self) -> Result<Element, BsonError> {
+        Ok(Element::Null)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Element, BsonError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Element, BsonError> {
+        Ok(Element::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Element, BsonError> {
+        Ok(Element::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Element, BsonError> {
+        Ok(Element::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Element, BsonError> {
+        match name {
+            OBJECT_ID_NEWTYPE | BINARY_NEWTYPE | DECIMAL128_NEWTYPE | DATETIME_NEWTYPE
+            | TIMESTAMP_NEWTYPE => special_element(name, value.serialize(self)?),
+            _ => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Element, BsonError> {
+        let mut builder = DocumentBuilder::new();
+        builder.try_append(variant, &value.serialize(self)?)?;
+        Ok(Element::EmbededDocument(builder.build()))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<ArraySerializer, BsonError> {
+        Ok(ArraySerializer {
+            builder: ArrayBuilder::new(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer, BsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<ArraySerializer, BsonError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<ArraySerializer>, BsonError> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<DocumentSerializer, BsonError> {
+        Ok(DocumentSerializer {
+            builder: DocumentBuilder::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<DocumentSerializer, BsonError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<DocumentSerializer>, BsonError> {
+        Ok(VariantSerializer {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+pub struct ArraySerializer {
+    builder: ArrayBuilder,
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BsonError> {
+        self.builder.try_append(&value.serialize(Serializer)?)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        Ok(Element::ArrayDocument(self.builder.build()))
+    }
+}
+
+impl ser::SerializeTuple for ArraySerializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BsonError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for ArraySerializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BsonError> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub struct DocumentSerializer {
+    builder: DocumentBuilder,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for DocumentSerializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), BsonError> {
+        let key = match key.serialize(Serializer)? {
+            Element::String(key) => key,
+            Element::Int32(key) => key.to_string(),
+            Element::Int64(key) => key.to_string(),
+            _ => {
+                return Err(BsonError::custom_message(
+                    "map key must be a string".to_string(),
+                ))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BsonError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| BsonError::custom_message("value serialized before key".to_string()))?;
+        self.builder
+            .try_append(&key, &value.serialize(Serializer)?)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        Ok(Element::EmbededDocument(self.builder.build()))
+    }
+}
+
+impl ser::SerializeStruct for DocumentSerializer {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), BsonError> {
+        self.builder
+            .try_append(key, &value.serialize(Serializer)?)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// wraps tuple and struct variants as `{ variant: ... }`
+pub struct VariantSerializer<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl<T> VariantSerializer<T> {
+    fn wrap(variant: &str, element: Element) -> Result<Element, BsonError> {
+        let mut builder = DocumentBuilder::new();
+        builder.try_append(variant, &element)?;
+        Ok(Element::EmbededDocument(builder.build()))
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<ArraySerializer> {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BsonError> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        let element = ser::SerializeSeq::end(self.inner)?;
+        Self::wrap(self.variant, element)
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<DocumentSerializer> {
+    type Ok = Element;
+    type Error = BsonError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), BsonError> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Element, BsonError> {
+        let element = ser::SerializeMap::end(self.inner)?;
+        Self::wrap(self.variant, element)
+    }
+}
@@ -1,4 +1,6 @@
+use super::builder::*;
 use super::element::*;
+use super::error::*;
 
 #[test]
 fn test_string_key() {
@@ -166,3 +168,1841 @@ fn test_binary() {
         binary
     );
 }
+
+#[test]
+fn test_encode_roundtrip() {
+    let value: &[u8] = &[
+        157, 0, 0, 0, 8, 98, 111, 111, 108, 0, 1, 2, 115, 116, 114, 105, 110, 103, 0, 7, 0, 0, 0,
+        115, 116, 114, 105, 110, 103, 0, 1, 102, 108, 111, 97, 116, 0, 92, 143, 194, 245, 40, 92,
+        11, 64, 4, 97, 114, 114, 97, 121, 0, 76, 0, 0, 0, 16, 48, 0, 1, 0, 0, 0, 8, 49, 0, 1, 8,
+        50, 0, 0, 1, 51, 0, 0, 0, 0, 0, 0, 0, 240, 63, 3, 52, 0, 20, 0, 0, 0, 2, 116, 101, 115,
+        116, 0, 5, 0, 0, 0, 116, 101, 115, 116, 0, 0, 16, 53, 0, 100, 0, 0, 0, 7, 54, 0, 98, 246,
+        223, 90, 2, 39, 224, 203, 106, 0, 169, 25, 0, 10, 110, 117, 108, 108, 0, 3, 100, 105, 99,
+        116, 0, 16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0, 0,
+    ];
+    let doc = Document::try_from(value).unwrap();
+    assert_eq!(value, doc.to_bytes());
+
+    let mut data = vec![];
+    for (name, element) in doc.iter() {
+        crate::encode::encode_element(&mut data, &name, &element);
+    }
+    assert_eq!(value, Document { data }.to_bytes());
+}
+
+#[test]
+fn test_encode_elements() {
+    let elements = [
+        Element::Binary(Binary {
+            binary_type: BinaryType::BinaryUuid,
+            data: [1; 16].to_vec(),
+        }),
+        Element::Javascript("function() {}".to_string()),
+        Element::Symbol("symbol".to_string()),
+        Element::Undefined,
+        Element::Int64(-42),
+        Element::Timestamp(crate::Timestamp::from(1 << 40)),
+        Element::Decimal(crate::Decimal128::from([7; 16])),
+        Element::Min,
+        Element::Max,
+    ];
+    for element in elements {
+        let mut data = vec![];
+        crate::encode::encode_element(&mut data, "a", &element);
+        let doc = Document { data };
+        let bytes = doc.to_bytes();
+        assert_eq!(
+            bytes.len() as i32,
+            i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        );
+        let decoded = Document::try_from(bytes.as_slice()).unwrap();
+        assert_eq!(Ok(element), decoded.get_any("a"));
+    }
+
+    let mut data = vec![];
+    Element::JavascriptCode(CodeWithScope {
+        code: "x".to_string(),
+        scope: Document { data: vec![] },
+    })
+    .encode_into(&mut data);
+    assert_eq!(vec![15, 0, 0, 0, 2, 0, 0, 0, 120, 0, 5, 0, 0, 0, 0], data);
+    let mut data = vec![];
+    Element::RegularExpression {
+        pattern: "^a".to_string(),
+        options: "i".to_string(),
+    }
+    .encode_into(&mut data);
+    assert_eq!(vec![94, 97, 0, 105, 0], data);
+}
+
+#[test]
+fn test_builder_complex() {
+    let value: &[u8] = &[
+        157, 0, 0, 0, 8, 98, 111, 111, 108, 0, 1, 2, 115, 116, 114, 105, 110, 103, 0, 7, 0, 0, 0,
+        115, 116, 114, 105, 110, 103, 0, 1, 102, 108, 111, 97, 116, 0, 92, 143, 194, 245, 40, 92,
+        11, 64, 4, 97, 114, 114, 97, 121, 0, 76, 0, 0, 0, 16, 48, 0, 1, 0, 0, 0, 8, 49, 0, 1, 8,
+        50, 0, 0, 1, 51, 0, 0, 0, 0, 0, 0, 0, 240, 63, 3, 52, 0, 20, 0, 0, 0, 2, 116, 101, 115,
+        116, 0, 5, 0, 0, 0, 116, 101, 115, 116, 0, 0, 16, 53, 0, 100, 0, 0, 0, 7, 54, 0, 98, 246,
+        223, 90, 2, 39, 224, 203, 106, 0, 169, 25, 0, 10, 110, 117, 108, 108, 0, 3, 100, 105, 99,
+        116, 0, 16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0, 0,
+    ];
+    let mut builder = DocumentBuilder::new();
+    builder
+        .append_bool("bool", true)
+        .append_string("string", "string")
+        .append_double("float", 3.42);
+    let mut array = builder.start_array("array");
+    array
+        .append_int32(1)
+        .append_bool(true)
+        .append_bool(false)
+        .append_double(1.0);
+    array.start_document().append_string("test", "test");
+    array.append_int32(100).append_object_id(ObjectId {
+        id: [
+            0x62, 0xf6, 0xdf, 0x5a, 0x02, 0x27, 0xe0, 0xcb, 0x6a, 0x00, 0xa9, 0x19,
+        ],
+    });
+    array.end();
+    builder.append_null("null");
+    builder.start_document("dict").append_string("hi", "hi");
+    assert_eq!(value, builder.build().to_bytes());
+}
+
+#[test]
+fn test_array_builder() {
+    let mut builder = ArrayBuilder::new();
+    builder.append_string("a").append_null();
+    builder.start_array().append_int64(2).append_int64(3);
+    builder.append(&Element::Max);
+    let array = builder.build();
+    assert_eq!(Ok("a".to_string()), array.get_string(0));
+    assert_eq!(Ok(true), array.is_null(1));
+    assert_eq!(Ok(3), array.get_array(2).unwrap().get_i64(1));
+    assert_eq!(Ok(true), array.is_max(3));
+    assert_eq!(
+        vec!["0", "1", "2", "3"],
+        array.iter().map(|(key, _)| key).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_doc_macro() {
+    let value: &[u8] = &[
+        157, 0, 0, 0, 8, 98, 111, 111, 108, 0, 1, 2, 115, 116, 114, 105, 110, 103, 0, 7, 0, 0, 0,
+        115, 116, 114, 105, 110, 103, 0, 1, 102, 108, 111, 97, 116, 0, 92, 143, 194, 245, 40, 92,
+        11, 64, 4, 97, 114, 114, 97, 121, 0, 76, 0, 0, 0, 16, 48, 0, 1, 0, 0, 0, 8, 49, 0, 1, 8,
+        50, 0, 0, 1, 51, 0, 0, 0, 0, 0, 0, 0, 240, 63, 3, 52, 0, 20, 0, 0, 0, 2, 116, 101, 115,
+        116, 0, 5, 0, 0, 0, 116, 101, 115, 116, 0, 0, 16, 53, 0, 100, 0, 0, 0, 7, 54, 0, 98, 246,
+        223, 90, 2, 39, 224, 203, 106, 0, 169, 25, 0, 10, 110, 117, 108, 108, 0, 3, 100, 105, 99,
+        116, 0, 16, 0, 0, 0, 2, 104, 105, 0, 3, 0, 0, 0, 104, 105, 0, 0, 0,
+    ];
+    let object_id = ObjectId {
+        id: [
+            0x62, 0xf6, 0xdf, 0x5a, 0x02, 0x27, 0xe0, 0xcb, 0x6a, 0x00, 0xa9, 0x19,
+        ],
+    };
+    let doc = crate::doc! {
+        "bool": true,
+        "string": "string",
+        "float": 3.42,
+        "array": [1, true, false, 1.0, { "test": "test" }, 100, object_id],
+        "null": null,
+        "dict": { "hi": "hi" }
+    };
+    assert_eq!(value, doc.to_bytes());
+}
+
+#[test]
+fn test_array_macro() {
+    let key = "nested";
+    let array = crate::array![
+        -1,
+        2 + 3,
+        i64::MAX,
+        None::<i32>,
+        crate::doc! { (key): crate::array![] },
+        Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: vec![1, 2],
+        },
+    ];
+    assert_eq!(Ok(-1), array.get_int32(0));
+    assert_eq!(Ok(5), array.get_int32(1));
+    assert_eq!(Ok(i64::MAX), array.get_i64(2));
+    assert_eq!(Ok(true), array.is_null(3));
+    let nested = array.get_document(4).unwrap().get_array("nested").unwrap();
+    assert_eq!(0, nested.iter().count());
+    assert_eq!(
+        Ok(vec![1, 2]),
+        array.get_binary(5).map(|binary| binary.data)
+    );
+    assert_eq!(crate::doc! {}, Document { data: vec![] });
+}
+
+#[test]
+fn test_edit_document() {
+    let mut doc = crate::doc! { "a": 1, "b": "two", "c": { "d": [1, 2, 3] } };
+    assert_eq!(
+        Ok(Some(Element::String("two".to_string()))),
+        doc.insert("b", Element::Double(2.0))
+    );
+    assert_eq!(Ok(None), doc.insert("e", Element::Null));
+    assert_eq!(Ok(()), doc.rename("a", "z"));
+    assert_eq!(
+        Err(ErrorKind::DuplicateKey),
+        doc.rename("z", "b").map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Err(ErrorKind::KeyNotFound),
+        doc.rename("a", "y").map_err(|e| e.kind)
+    );
+    {
+        let mut c = doc.get_document_mut("c").unwrap();
+        let mut d = c.get_array_mut("d").unwrap();
+        assert_eq!(Ok(Element::Int32(2)), d.remove(1));
+        d.push(Element::String("four".to_string()));
+        assert_eq!(Ok(Element::Int32(3)), d.set(1, Element::Int64(3)));
+        drop(d);
+        c.insert("x", Element::Boolean(false)).unwrap();
+    }
+    assert_eq!(
+        crate::doc! {
+            "z": 1,
+            "b": 2.0,
+            "c": { "d": [1, 3i64, "four"], "x": false },
+            "e": null
+        },
+        doc
+    );
+    assert_eq!(Ok(Element::Int32(1)), doc.remove("z"));
+    assert_eq!(
+        Err(BsonError::new(ErrorKind::KeyNotFound).with_key("z")),
+        doc.remove("z")
+    );
+    assert_eq!(
+        "expected array, found double at `b`",
+        doc.get_array_mut("b").unwrap_err().to_string()
+    );
+    let bytes = doc.to_bytes();
+    assert_eq!(Ok(doc.clone()), Document::try_from(bytes.as_slice()));
+
+    // keys and regular expressions are cstrings, a null byte in them is refused
+    let regex = Element::RegularExpression {
+        pattern: "a\0b".to_string(),
+        options: String::new(),
+    };
+    assert_eq!(
+        Err(ErrorKind::NullInCString),
+        doc.insert("a\0b", Element::Null).map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Err(ErrorKind::NullInCString),
+        doc.insert("r", regex.clone()).map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Err(ErrorKind::NullInCString),
+        doc.rename("b", "a\0b").map_err(|e| e.kind)
+    );
+    assert_eq!(bytes, doc.to_bytes());
+    let mut builder = DocumentBuilder::new();
+    let error = builder.try_append("a\0b", &Element::Int32(1)).unwrap_err();
+    assert_eq!(Some("a\0b".to_string()), error.path);
+    assert!(builder.try_append("r", &regex).is_err());
+    assert!(ArrayBuilder::new().try_append(&regex).is_err());
+    assert_eq!(crate::doc! {}, builder.build());
+    assert!(std::panic::catch_unwind(|| crate::doc! { "a\0b": 1 }).is_err());
+    // strings are length prefixed and keep their null bytes
+    assert!(Document::validate(&crate::doc! { "s": "a\0b" }.to_bytes()).is_ok());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    use crate::{DateTime, Decimal128, Timestamp};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Kind {
+        Plain,
+        Tuple(i32, String),
+        Struct { flag: bool },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        id: ObjectId,
+        name: String,
+        count: u32,
+        big: i64,
+        ratio: f64,
+        tags: Vec<String>,
+        raw: Binary,
+        amount: Decimal128,
+        created: DateTime,
+        ts: Timestamp,
+        missing: Option<i32>,
+        kinds: Vec<Kind>,
+        extra: BTreeMap<String, bool>,
+    }
+
+    let record = Record {
+        id: [1; 12].into(),
+        name: "name".to_string(),
+        count: u32::MAX,
+        big: -5,
+        ratio: 0.5,
+        tags: vec!["a".to_string(), "b".to_string()],
+        raw: Binary {
+            binary_type: BinaryType::BinaryUuid,
+            data: vec![4; 16],
+        },
+        amount: [3; 16].into(),
+        created: DateTime {
+            millis: 1_660_000_000_000,
+        },
+        ts: Timestamp {
+            time: u32::MAX,
+            increment: 7,
+        },
+        missing: None,
+        kinds: vec![
+            Kind::Plain,
+            Kind::Tuple(1, "x".to_string()),
+            Kind::Struct { flag: true },
+        ],
+        extra: [("yes".to_string(), true)].into_iter().collect(),
+    };
+    let doc = crate::to_document(&record).unwrap();
+    assert_eq!(Ok([1; 12].into()), doc.get_object_id("id"));
+    assert_eq!(Ok(u32::MAX as i64), doc.get_i64("count"));
+    assert_eq!(
+        Ok(BinaryType::BinaryUuid),
+        doc.get_binary("raw").map(|b| b.binary_type)
+    );
+    assert_eq!(Ok(Decimal128::from([3; 16])), doc.get_decimal128("amount"));
+    assert_eq!(
+        Ok(DateTime::from(1_660_000_000_000)),
+        doc.get_datetime("created")
+    );
+    assert_eq!(
+        Ok(((u32::MAX as u64) << 32 | 7).into()),
+        doc.get_timestamp("ts")
+    );
+    assert_eq!(Ok(true), doc.is_null("missing"));
+    assert_eq!(
+        Ok("Plain".to_string()),
+        doc.get_array("kinds").unwrap().get_string(0)
+    );
+
+    assert_eq!(Ok(&record), crate::from_document::<Record>(&doc).as_ref());
+    assert_eq!(Ok(record), crate::from_slice::<Record>(&doc.to_bytes()));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_errors() {
+    #[derive(serde::Serialize, serde::Deserialize, Debug)]
+    struct Small {
+        value: u8,
+    }
+    assert!(crate::from_document::<Small>(&crate::doc! { "value": 300 }).is_err());
+    let map = std::collections::BTreeMap::from([("a\0b", 1)]);
+    assert_eq!(
+        Err(ErrorKind::NullInCString),
+        crate::to_document(&map).map_err(|e| e.kind)
+    );
+    #[derive(serde::Deserialize, Debug)]
+    struct Outer {
+        #[allow(dead_code)]
+        items: Vec<Small>,
+    }
+    let error =
+        crate::from_document::<Outer>(&crate::doc! { "items": [{ "value": 1 }, { "value": "x" }] })
+            .unwrap_err();
+    assert_eq!(Some("items.1.value".to_string()), error.path);
+    assert!(crate::from_document::<Small>(&crate::doc! { "other": 1 }).is_err());
+    assert!(crate::to_document(&5).is_err());
+    assert!(crate::to_document(&[(1.5f64, 1)]).is_err());
+}
+
+#[test]
+fn test_extended_json() {
+    use crate::{Decimal128, ExtendedJsonMode::*};
+
+    let doc = crate::doc! {
+        "s": "a\"b\\\n\u{1}",
+        "d": 1.0,
+        "e": 1.2345678921232e18,
+        "nan": f64::NAN,
+        "oid": ObjectId { id: [0x62, 0xf5, 0xf9, 0xca, 0x18, 0xe4, 0xea, 0xdb, 0x8e, 0xa0, 0xf7, 0x5b] },
+        "bin": Binary { binary_type: BinaryType::BinaryUserDefined(0x80), data: vec![0xff, 0xff] },
+        "date": crate::DateTime::from(1_356_351_330_501),
+        "old": crate::DateTime::from(-284_643_869_501),
+        "ts": crate::Timestamp { time: 123_456_789, increment: 42 },
+        "dec": Decimal128::from([0xD2, 0x04, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x34, 0x30]),
+        "keys": [Element::Min, Element::Max, Element::Undefined, null],
+    };
+    assert_eq!(
+        concat!(
+            r#"{"s": "a\"b\\\n\u0001", "d": 1.0, "e": 1.2345678921232E+18, "nan": {"$numberDouble": "NaN"}, "#,
+            r#""oid": {"$oid": "62f5f9ca18e4eadb8ea0f75b"}, "bin": {"$binary": {"base64": "//8=", "subType": "80"}}, "#,
+            r#""date": {"$date": "2012-12-24T12:15:30.501Z"}, "old": {"$date": {"$numberLong": "-284643869501"}}, "#,
+            r#""ts": {"$timestamp": {"t": 123456789, "i": 42}}, "#,
+            r#""dec": {"$numberDecimal": "0.001234"}, "#,
+            r#""keys": [{"$minKey": 1}, {"$maxKey": 1}, {"$undefined": true}, null]}"#
+        ),
+        doc.to_extended_json(Relaxed)
+    );
+    assert_eq!(
+        r#"{"d": {"$numberDouble": "1.0"}, "date": {"$date": {"$numberLong": "0"}}, "n": [{"$numberLong": "-1"}]}"#,
+        crate::doc! { "d": 1.0, "date": crate::DateTime::from(0), "n": [-1i64] }
+            .to_extended_json(Canonical)
+    );
+    assert_eq!(
+        r#"{"$regularExpression": {"pattern": "^a", "options": "i"}}"#,
+        Element::RegularExpression {
+            pattern: "^a".to_string(),
+            options: "i".to_string()
+        }
+        .to_extended_json(Canonical)
+    );
+    assert_eq!(
+        r#"{"$code": "x", "$scope": {"y": {"$numberInt": "1"}}}"#,
+        Element::JavascriptCode(CodeWithScope {
+            code: "x".to_string(),
+            scope: crate::doc! { "y": 1 },
+        })
+        .to_extended_json(Canonical)
+    );
+    assert_eq!(
+        r#"{"date": {"$date": "1970-01-01T00:00:00Z"}}"#,
+        format!("{}", crate::doc! { "date": crate::DateTime::from(0) })
+    );
+}
+
+#[test]
+fn test_decimal128_display() {
+    use crate::Decimal128;
+
+    let cases: [(u128, &str); 8] = [
+        (0x7C00_0000_0000_0000_0000_0000_0000_0000, "NaN"),
+        (0xF800_0000_0000_0000_0000_0000_0000_0000, "-Infinity"),
+        (0x3040_0000_0000_0000_0000_0000_0000_0000, "0"),
+        (0x3040_0000_0000_0000_0000_0000_0000_0001, "1"),
+        (0x3046_0000_0000_0000_0000_0000_0000_0001, "1E+3"),
+        (0x303A_0000_0000_0000_0000_0000_0000_07D0, "2.000"),
+        (0x8000_0000_0000_0000_0000_0000_0000_0001, "-1E-6176"),
+        (0x6C10_0000_0000_0000_0000_0000_0000_0000, "0"),
+    ];
+    for (bits, expected) in cases {
+        assert_eq!(expected, Decimal128::from(bits.to_le_bytes()).to_string());
+    }
+}
+
+#[test]
+fn test_extended_json_parse() {
+    use crate::ExtendedJsonMode::*;
+
+    let doc = crate::doc! {
+        "s": "a\"b\\\n\u{1}\u{1F600}",
+        "d": 1.0,
+        "e": 1.2345678921232e18,
+        "i": 1,
+        "l": 1i64 << 40,
+        "neg": -0.0,
+        "oid": ObjectId { id: [0x62, 0xf5, 0xf9, 0xca, 0x18, 0xe4, 0xea, 0xdb, 0x8e, 0xa0, 0xf7, 0x5b] },
+        "bin": Binary { binary_type: BinaryType::BinaryUserDefined(0x80), data: vec![0xff, 0xff] },
+        "date": crate::DateTime::from(1_356_351_330_501),
+        "old": crate::DateTime::from(-284_643_869_501),
+        "ts": crate::Timestamp { time: 123_456_789, increment: 42 },
+        "dec": crate::Decimal128::from([0xD2, 0x04, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x34, 0x30]),
+        "nested": { "keys": [Element::Min, Element::Max, Element::Undefined, null] },
+    };
+    for mode in [Canonical, Relaxed] {
+        let parsed = Document::from_extended_json(&doc.to_extended_json(mode)).unwrap();
+        assert_eq!(doc.to_bytes(), parsed.to_bytes());
+    }
+
+    let mut builder = DocumentBuilder::new();
+    builder
+        .append(
+            "re",
+            &Element::RegularExpression {
+                pattern: "^a".to_string(),
+                options: "i".to_string(),
+            },
+        )
+        .append(
+            "code",
+            &Element::JavascriptCode(CodeWithScope {
+                code: "x".to_string(),
+                scope: crate::doc! { "y": 1 },
+            }),
+        )
+        .append_datetime("date", crate::DateTime::from(1_356_351_330_000))
+        .append_string("query", "$regex");
+    let mut query = builder.start_document("q");
+    query.append_string("$regex", "^b");
+    query.end();
+    assert_eq!(
+        builder.build(),
+        Document::from_extended_json(concat!(
+            r#"{"re": {"$regex": "^a", "$options": "i"}, "#,
+            r#""code": {"$code": "x", "$scope": {"y": 1}}, "#,
+            r#""date": {"$date": "2012-12-24T13:15:30+01:00"}, "query": "$regex", "#,
+            r#""q": {"$regex": "^b"}}"#
+        ))
+        .unwrap()
+    );
+
+    let error = |json: &str| Document::from_extended_json(json).unwrap_err().to_string();
+    assert_eq!(
+        "invalid extended json: expected `,` or `}` at line 2 column 4",
+        error("{\"a\": 1\n   \"b\": 2}")
+    );
+    assert_eq!(
+        "invalid extended json: $numberInt: `$numberInt` must be a string, found a number at `a.b`",
+        error(r#"{"a": {"b": {"$numberInt": 1}}}"#)
+    );
+    assert_eq!(
+        "invalid extended json: $oid: unexpected field `x` at `a`",
+        error(r#"{"a": {"$oid": "62f5f9ca18e4eadb8ea0f75b", "x": 1}}"#)
+    );
+    assert_eq!(
+        "invalid extended json: $binary: missing field `subType` at `a.0`",
+        error(r#"{"a": [{"$binary": {"base64": ""}}]}"#)
+    );
+    assert_eq!(
+        "invalid extended json: $numberInt: expected a 32-bit integer at `a`",
+        error(r#"{"a": {"$numberInt": "2147483648"}}"#)
+    );
+    assert_eq!(
+        "invalid extended json: unpaired surrogate in unicode escape at line 1 column 14",
+        error(r#"{"a": "\ud800"}"#)
+    );
+    assert!(Document::from_extended_json("[1]").is_err());
+}
+
+#[test]
+fn test_decimal128_parse() {
+    use crate::Decimal128;
+
+    let cases: [(&str, u128); 8] = [
+        ("NaN", 0x7C00_0000_0000_0000_0000_0000_0000_0000),
+        ("-inf", 0xF800_0000_0000_0000_0000_0000_0000_0000),
+        ("0", 0x3040_0000_0000_0000_0000_0000_0000_0000),
+        ("1E+3", 0x3046_0000_0000_0000_0000_0000_0000_0001),
+        ("2.000", 0x303A_0000_0000_0000_0000_0000_0000_07D0),
+        ("-1E-6176", 0x8000_0000_0000_0000_0000_0000_0000_0001),
+        ("0E+6112", 0x5FFE_0000_0000_0000_0000_0000_0000_0000),
+        ("1E6112", 0x5FFE_0000_0000_0000_0000_0000_0000_000A),
+    ];
+    for (text, bits) in cases {
+        let parsed = text.parse::<Decimal128>().map(|decimal| decimal.bytes);
+        assert_eq!(Ok(bits.to_le_bytes()), parsed);
+    }
+    assert!("1E-6177".parse::<Decimal128>().is_err());
+    assert!("1.2.3".parse::<Decimal128>().is_err());
+}
+
+#[test]
+fn test_decimal128_compare_and_convert() {
+    use crate::Decimal128;
+    let decimal = |text: &str| text.parse::<Decimal128>().unwrap();
+
+    let ascending = [
+        "-Infinity",
+        "-1E+10",
+        "-1",
+        "0",
+        "1E-6176",
+        "0.999",
+        "1",
+        "1E+10",
+    ];
+    for pair in ascending.windows(2) {
+        assert!(decimal(pair[0]) < decimal(pair[1]), "{pair:?}");
+    }
+    assert!(decimal("Infinity") > decimal("9.999999999999999999999999999999999E+6144"));
+    assert_eq!(decimal("1.0"), decimal("1.00"));
+    assert_eq!(decimal("-0E+5"), decimal("0"));
+    assert_ne!(decimal("NaN"), decimal("NaN"));
+    assert_eq!(None, decimal("NaN").partial_cmp(&decimal("1")));
+
+    assert_eq!(Ok(i64::MIN), i64::try_from(Decimal128::from(i64::MIN)));
+    assert_eq!(Ok(1200), i64::try_from(decimal("12E+2")));
+    assert_eq!(Ok(-3), i64::try_from(decimal("-3.000")));
+    assert_eq!(Ok(0), i64::try_from(decimal("0E-6176")));
+    assert!(i64::try_from(decimal("1.5")).is_err());
+    assert!(i64::try_from(decimal("9223372036854775808")).is_err());
+    assert!(i64::try_from(decimal("Infinity")).is_err());
+
+    assert_eq!("0.1", Decimal128::from(0.1).to_string());
+    assert_eq!("-1E+300", Decimal128::from(-1e300).to_string());
+    assert!(Decimal128::from(f64::NAN).is_nan());
+    assert_eq!(f64::NEG_INFINITY, decimal("-Infinity").to_f64());
+    assert_eq!(0.0, decimal("1E-6176").to_f64());
+    assert_eq!(Ok(0.1), f64::try_from(decimal("0.1")));
+    assert!(f64::try_from(decimal("0.10000000000000000001")).is_err());
+    assert!(f64::try_from(decimal("1E-6176")).is_err());
+}
+
+#[test]
+fn test_document_ref() {
+    let doc = crate::doc! {
+        "name": "bson",
+        "bin": Binary { binary_type: BinaryType::BinaryGeneric, data: vec![1, 2] },
+        "nested": { "list": [1, "two", { "three": 3.0 }] },
+    };
+    let bytes = doc.to_bytes();
+    let doc_ref = crate::DocumentRef::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(Ok("bson"), doc_ref.get_str("name"));
+    assert_eq!(Ok(&[1u8, 2][..]), doc_ref.get_binary("bin").map(|b| b.data));
+
+    let list = doc_ref
+        .get_document("nested")
+        .unwrap()
+        .get_array("list")
+        .unwrap();
+    assert_eq!(Ok("two"), list.get_str(1));
+    assert_eq!(Ok(3.0), list.get_document(2).unwrap().get_float("three"));
+    assert_eq!(
+        Err(ErrorKind::KeyNotFound),
+        list.get_any(3).map_err(|e| e.kind)
+    );
+    assert_eq!(
+        "expected int, found string at `1`",
+        list.get_int32(1).unwrap_err().to_string()
+    );
+
+    let keys: Vec<&str> = doc_ref.iter().map(|(key, _)| key).collect();
+    assert_eq!(vec!["name", "bin", "nested"], keys);
+    for ((key, element), (owned_key, owned)) in doc_ref.iter().zip(doc.iter()) {
+        assert_eq!(owned_key, key);
+        assert_eq!(owned, element.to_owned());
+    }
+    assert_eq!(doc, doc_ref.to_owned());
+}
+
+#[test]
+fn test_error_context() {
+    let doc = crate::doc! { "a": 1, "s": "text", "n": { "x": 1 } };
+    let mut bytes = doc.to_bytes();
+    // length prefix (4), "a" element (7), "s" type and key (3)
+    let string_length = 4 + 7 + 3;
+
+    bytes[string_length] = 0;
+    let broken = Document::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        BsonError {
+            kind: ErrorKind::BadLength(0),
+            offset: Some(string_length),
+            path: Some("s".to_string()),
+            element_type: Some(0x02),
+        },
+        broken.get_int32("n").unwrap_err()
+    );
+
+    bytes[string_length] = 0xFF;
+    let broken = Document::try_from(bytes.as_slice()).unwrap();
+    let error = broken.get_int32("n").unwrap_err();
+    assert_eq!((ErrorKind::Truncated, Some(18)), (error.kind, error.offset));
+
+    bytes[string_length] = 5;
+    bytes[string_length + 4] = 0xC3;
+    let broken = Document::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        "invalid utf-8 at `s` (offset 18, element type 0x02)",
+        broken.get_string("s").unwrap_err().to_string()
+    );
+
+    bytes[4] = 0x42;
+    let broken = Document::try_from(bytes.as_slice()).unwrap();
+    let error = broken.get_string("s").unwrap_err();
+    assert_eq!(ErrorKind::UnknownType, error.kind);
+    assert_eq!((Some(4), Some(0x42)), (error.offset, error.element_type));
+
+    let error = Document::try_from(&bytes[..10]).unwrap_err();
+    assert_eq!((ErrorKind::Truncated, Some(4)), (error.kind, error.offset));
+    let error = Document::try_from(&[3u8, 0, 0, 0][..]).unwrap_err();
+    assert_eq!(
+        (ErrorKind::BadLength(3), Some(0)),
+        (error.kind, error.offset)
+    );
+}
+
+#[test]
+fn test_validate() {
+    let doc = crate::doc! { "a": "x", "n": { "b": true, "l": [1, 2] } };
+    let bytes = doc.to_bytes();
+    assert_eq!(Ok(doc), Document::try_from_validated(&bytes));
+
+    let kind = |bytes: &[u8]| Document::validate(bytes).map_err(|e| (e.kind, e.offset, e.path));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        Err((ErrorKind::BadLength(bytes.len() as i32), Some(0), None)),
+        kind(&trailing)
+    );
+    assert_eq!(
+        Err((ErrorKind::Truncated, Some(bytes.len() - 1), None)),
+        kind(&bytes[..bytes.len() - 1])
+    );
+
+    let mut no_terminator = bytes.clone();
+    *no_terminator.last_mut().unwrap() = 1;
+    assert_eq!(
+        Err((ErrorKind::Malformed, Some(bytes.len() - 1), None)),
+        kind(&no_terminator)
+    );
+
+    // "x" is followed by its NUL at offset 12
+    let mut string = bytes.clone();
+    string[12] = b'y';
+    assert_eq!(
+        Err((ErrorKind::Malformed, Some(12), Some("a".to_string()))),
+        kind(&string)
+    );
+
+    // boolean value of "n.b" at offset 23
+    let mut boolean = bytes.clone();
+    boolean[23] = 2;
+    assert_eq!(
+        Err((ErrorKind::Malformed, Some(23), Some("n.b".to_string()))),
+        kind(&boolean)
+    );
+
+    // the trailing NUL of "n.l" at offset 45
+    let mut nested = bytes.clone();
+    nested[45] = 0x0A;
+    assert_eq!(
+        Err((ErrorKind::Malformed, Some(45), Some("n.l".to_string()))),
+        kind(&nested)
+    );
+
+    let mut deep = DocumentBuilder::new();
+    deep.append_int32("x", 1);
+    let mut deep = deep.build();
+    for _ in 0..=crate::MAX_NESTING_DEPTH {
+        let mut parent = DocumentBuilder::new();
+        parent.append_document("d", &deep);
+        deep = parent.build();
+    }
+    assert_eq!(
+        ErrorKind::NestingTooDeep,
+        Document::validate(&deep.to_bytes()).unwrap_err().kind
+    );
+}
+
+/// touches every decoding entry point, hostile input may fail but must not panic
+fn decode_everything(bytes: &[u8]) {
+    fn walk(doc: &Document) {
+        for (key, element) in doc.iter() {
+            let _ = doc.get_any(&key);
+            match element {
+                Element::EmbededDocument(doc) => walk(&doc),
+                Element::JavascriptCode(value) => walk(&value.scope),
+                Element::ArrayDocument(array) => walk(&Document { data: array.data }),
+                _ => {}
+            }
+        }
+        let _ = doc.to_extended_json(crate::ExtendedJsonMode::Canonical);
+    }
+    fn walk_ref(doc: crate::DocumentRef) {
+        for (_, element) in doc.iter() {
+            match element {
+                crate::ElementRef::EmbededDocument(doc) => walk_ref(doc),
+                crate::ElementRef::ArrayDocument(array) => {
+                    walk_ref(crate::DocumentRef { data: array.data })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let _ = Document::validate(bytes);
+    if let Ok(doc) = Document::try_from(bytes) {
+        walk(&doc);
+        let mut input = doc.data.as_slice();
+        while let Ok((rest, _)) = crate::parse::parse_any(input) {
+            input = rest;
+        }
+    }
+    if let Ok(doc) = crate::DocumentRef::try_from(bytes) {
+        walk_ref(doc);
+    }
+}
+
+#[test]
+fn test_hostile_input_does_not_panic() {
+    let mut builder = DocumentBuilder::new();
+    builder
+        .append_double("d", 1.5)
+        .append_string("s", "text")
+        .append_document("o", &crate::doc! { "x": [1, "y"] })
+        .append_binary(
+            "b",
+            &Binary {
+                binary_type: BinaryType::BinaryGeneric,
+                data: vec![1, 2, 3],
+            },
+        )
+        .append_undefined("u")
+        .append_object_id("i", ObjectId { id: [7; 12] })
+        .append_bool("t", true)
+        .append_datetime("dt", crate::DateTime::from(-1))
+        .append_null("n")
+        .append(
+            "re",
+            &Element::RegularExpression {
+                pattern: "a".to_string(),
+                options: "i".to_string(),
+            },
+        )
+        .append(
+            "p",
+            &Element::DbPointer(crate::DbPointer {
+                namespace: "db.c".to_string(),
+                id: ObjectId { id: [9; 12] },
+            }),
+        )
+        .append_javascript("js", "f()")
+        .append_symbol("sy", "sym")
+        .append(
+            "cws",
+            &Element::JavascriptCode(CodeWithScope {
+                code: "g()".to_string(),
+                scope: crate::doc! { "z": 1 },
+            }),
+        )
+        .append_int32("i32", -2)
+        .append_timestamp("ts", crate::Timestamp::from(42))
+        .append_int64("i64", 1 << 40)
+        .append_decimal128("dec", crate::Decimal128::from([0; 16]))
+        .append_min("min")
+        .append_max("max");
+    let bytes = builder.build().to_bytes();
+
+    for len in 0..bytes.len() {
+        decode_everything(&bytes[..len]);
+    }
+    for position in 0..bytes.len() {
+        for value in [0x00, 0x01, 0x05, 0x7F, 0x80, 0xFF, !bytes[position]] {
+            let mut mutated = bytes.clone();
+            mutated[position] = value;
+            decode_everything(&mutated);
+        }
+    }
+}
+
+/// bson corpus entries this crate does not handle yet, as (file, description)
+const CORPUS_KNOWN_FAILURES: &[(&str, &str)] = &[];
+
+/// decodes and re-encodes every element, normalizing degenerate array keys and regular
+/// expression options on the way
+fn reencode(doc: &Document) -> Document {
+    fn normalize(element: Element) -> Element {
+        match element {
+            Element::EmbededDocument(doc) => Element::EmbededDocument(reencode(&doc)),
+            Element::ArrayDocument(array) => {
+                let mut builder = ArrayBuilder::new();
+                for (_, item) in array.iter() {
+                    builder.append(&normalize(item));
+                }
+                Element::ArrayDocument(builder.build())
+            }
+            Element::RegularExpression { pattern, options } => {
+                crate::Regex::new(pattern, &options).unwrap().into()
+            }
+            other => other,
+        }
+    }
+    let mut builder = DocumentBuilder::new();
+    for (key, item) in doc.iter() {
+        builder.append(&key, &normalize(item));
+    }
+    builder.build()
+}
+
+fn corpus_bytes(value: &serde_json::Value) -> Vec<u8> {
+    hex::decode(value.as_str().unwrap()).unwrap()
+}
+
+fn same_json(expected: &str, actual: &str) -> bool {
+    let expected: serde_json::Value = serde_json::from_str(expected).unwrap();
+    serde_json::from_str::<serde_json::Value>(actual).is_ok_and(|actual| actual == expected)
+}
+
+/// checks one `valid` entry, returns the first mismatch
+fn corpus_valid_case(case: &serde_json::Value) -> Result<(), String> {
+    let canonical_bson = corpus_bytes(&case["canonical_bson"]);
+    let canonical_extjson = case["canonical_extjson"].as_str().unwrap();
+    let lossy = case["lossy"].as_bool().unwrap_or(false);
+
+    Document::validate(&canonical_bson).map_err(|err| format!("validate: {err}"))?;
+    let doc = Document::try_from(canonical_bson.as_slice()).map_err(|err| format!("{err}"))?;
+    if reencode(&doc).to_bytes() != canonical_bson {
+        return Err("canonical_bson does not round trip".to_string());
+    }
+    let json = doc.to_extended_json(crate::ExtendedJsonMode::Canonical);
+    if !same_json(canonical_extjson, &json) {
+        return Err(format!("canonical_extjson mismatch: {json}"));
+    }
+    if let Some(relaxed_extjson) = case["relaxed_extjson"].as_str() {
+        let json = doc.to_extended_json(crate::ExtendedJsonMode::Relaxed);
+        if !same_json(relaxed_extjson, &json) {
+            return Err(format!("relaxed_extjson mismatch: {json}"));
+        }
+        let parsed = Document::from_extended_json(relaxed_extjson)
+            .map_err(|err| format!("relaxed_extjson: {err}"))?;
+        let json = parsed.to_extended_json(crate::ExtendedJsonMode::Relaxed);
+        if !same_json(relaxed_extjson, &json) {
+            return Err(format!("relaxed_extjson does not round trip: {json}"));
+        }
+    }
+    if !lossy {
+        let parsed = Document::from_extended_json(canonical_extjson)
+            .map_err(|err| format!("canonical_extjson: {err}"))?;
+        if parsed.to_bytes() != canonical_bson {
+            return Err("canonical_extjson does not encode to canonical_bson".to_string());
+        }
+    }
+    if let Some(degenerate_bson) = case.get("degenerate_bson") {
+        let degenerate_bson = corpus_bytes(degenerate_bson);
+        let doc = Document::try_from(degenerate_bson.as_slice())
+            .map_err(|err| format!("degenerate_bson: {err}"))?;
+        if reencode(&doc).to_bytes() != canonical_bson {
+            return Err("degenerate_bson does not encode to canonical_bson".to_string());
+        }
+    }
+    if let (Some(degenerate_extjson), false) = (case["degenerate_extjson"].as_str(), lossy) {
+        let parsed = Document::from_extended_json(degenerate_extjson)
+            .map_err(|err| format!("degenerate_extjson: {err}"))?;
+        if parsed.to_bytes() != canonical_bson {
+            return Err("degenerate_extjson does not encode to canonical_bson".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// runs the bson specification corpus vendored in `tests/bson-corpus`
+#[test]
+fn test_bson_corpus() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/bson-corpus");
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let mut failures = Vec::new();
+    for path in paths {
+        let file = path.file_name().unwrap().to_str().unwrap().to_string();
+        let suite: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let empty = Vec::new();
+        let cases = |name: &str| suite[name].as_array().unwrap_or(&empty).clone();
+        let mut check = |description: &str, result: Result<(), String>| {
+            let known = CORPUS_KNOWN_FAILURES.contains(&(file.as_str(), description));
+            match (result, known) {
+                (Err(message), false) => failures.push(format!("{file}: {description}: {message}")),
+                (Ok(()), true) => failures.push(format!("{file}: {description}: passes now")),
+                _ => {}
+            }
+        };
+
+        for case in cases("valid") {
+            check(
+                case["description"].as_str().unwrap(),
+                corpus_valid_case(&case),
+            );
+        }
+        for case in cases("decodeErrors") {
+            let bytes = corpus_bytes(&case["bson"]);
+            let result = match Document::validate(&bytes) {
+                Ok(()) => Err("decoded without error".to_string()),
+                Err(_) => Ok(()),
+            };
+            check(case["description"].as_str().unwrap(), result);
+        }
+        for case in cases("parseErrors") {
+            let input = case["string"].as_str().unwrap();
+            let parsed = if suite["bson_type"] == "0x13" {
+                input.parse::<crate::Decimal128>().is_ok()
+            } else {
+                Document::from_extended_json(input).is_ok()
+            };
+            let result = match parsed {
+                true => Err("parsed without error".to_string()),
+                false => Ok(()),
+            };
+            check(case["description"].as_str().unwrap(), result);
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_datetime() {
+    use crate::DateTime;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    let doc = crate::doc! { "before": DateTime::from(-1), "after": DateTime::from(1_500) };
+    assert_eq!(Ok(DateTime::from(-1)), doc.get_datetime("before"));
+    assert_eq!(
+        "1969-12-31T23:59:59.999Z",
+        doc.get_datetime("before").unwrap().to_string()
+    );
+    assert_eq!(
+        Ok(Element::DateTime(DateTime::from(1_500))),
+        doc.get_any("after")
+    );
+
+    let micros = Duration::from_micros(1_500);
+    assert_eq!(DateTime::from(1), DateTime::from(UNIX_EPOCH + micros));
+    assert_eq!(DateTime::from(-2), DateTime::from(UNIX_EPOCH - micros));
+    let time = SystemTime::try_from(DateTime::from(-86_400_000)).unwrap();
+    assert_eq!(
+        Ok(Duration::from_secs(86_400)),
+        UNIX_EPOCH.duration_since(time).map_err(|_| ())
+    );
+    assert!(DateTime::now() > DateTime::from(1_600_000_000_000));
+
+    let first = DateTime::parse_rfc3339("0000-01-01T00:00:00Z").unwrap();
+    assert_eq!(
+        Ok("0000-01-01T00:00:00.000Z".to_string()),
+        first.to_rfc3339()
+    );
+    assert!(DateTime::from(first.millis - 1).to_rfc3339().is_err());
+    let last = DateTime::parse_rfc3339("9999-12-31T23:59:59.999999Z").unwrap();
+    assert_eq!(
+        Ok("9999-12-31T23:59:59.999Z".to_string()),
+        last.to_rfc3339()
+    );
+    assert!(DateTime::from(last.millis + 1).to_rfc3339().is_err());
+    assert_eq!(
+        DateTime::parse_rfc3339("2020-01-01T01:30:00+01:30"),
+        DateTime::parse_rfc3339("2020-01-01T00:00:00Z")
+    );
+    assert_eq!(
+        DateTime::parse_rfc3339("2020-01-01T00:00:00.123Z"),
+        DateTime::parse_rfc3339("2020-01-01T00:00:00.12345678901234Z")
+    );
+    assert!(DateTime::parse_rfc3339("2020-01-01T00:00:00.Z").is_err());
+    assert!(DateTime::parse_rfc3339("2023-02-29T00:00:00Z").is_err());
+    assert!(DateTime::parse_rfc3339("2020-01-01T00:00:00+24:00").is_err());
+    for offset in ["+0:130", "+0130", "+01:3:0", "+01:", "-1:30"] {
+        let date = format!("2020-01-01T00:00:00{offset}");
+        assert!(DateTime::parse_rfc3339(&date).is_err(), "{date}");
+    }
+    assert_eq!("DateTime(-9223372036854775808)", DateTime::MIN.to_string());
+}
+
+#[test]
+fn test_timestamp() {
+    use crate::Timestamp;
+
+    let late = Timestamp {
+        time: 0x8000_0000,
+        increment: 0,
+    };
+    let early = Timestamp {
+        time: 0x7FFF_FFFF,
+        increment: u32::MAX,
+    };
+    assert!(early < late);
+    assert_eq!(0x8000_0000_0000_0000, u64::from(late));
+    assert_eq!(early, Timestamp::from(0x7FFF_FFFF_FFFF_FFFF));
+
+    let doc = crate::doc! { "ts": early };
+    assert_eq!(Ok(early), doc.get_timestamp("ts"));
+    assert_eq!(Ok(early), doc.get_any("ts").and_then(|e| e.as_timestamp()));
+    assert_eq!(
+        Ok(early),
+        crate::DocumentRef::from(&doc).get_timestamp("ts")
+    );
+    let json = r#"{"ts": {"$timestamp": {"t": 2147483647, "i": 4294967295}}}"#;
+    assert_eq!(
+        json,
+        doc.to_extended_json(crate::ExtendedJsonMode::Canonical)
+    );
+    assert_eq!(Ok(doc), Document::from_extended_json(json));
+}
+
+#[test]
+fn test_uuid() {
+    use crate::{Uuid, UuidRepresentation::*};
+
+    let id: Uuid = "00112233-4455-6677-8899-aabbccddeeff".parse().unwrap();
+    assert_eq!(Ok(id), "00112233445566778899AABBCCDDEEFF".parse());
+    assert!("00112233-4455-6677-8899-aabbccddeef"
+        .parse::<Uuid>()
+        .is_err());
+    let legacy = [
+        (JavaLegacy, "7766554433221100ffeeddccbbaa9988"),
+        (CSharpLegacy, "33221100554477668899aabbccddeeff"),
+        (PythonLegacy, "00112233445566778899aabbccddeeff"),
+    ];
+    for (representation, expected) in legacy {
+        let binary = id.to_binary(representation);
+        assert_eq!(BinaryType::BinaryOldUuid, binary.binary_type);
+        assert_eq!(expected, hex::encode(&binary.data));
+        assert_eq!(Ok(id), Uuid::from_binary(&binary, representation));
+    }
+
+    let doc = crate::doc! {
+        "java": id.to_binary(JavaLegacy),
+        "nested": { "list": [id.to_binary(JavaLegacy), id] },
+        "other": Binary { binary_type: BinaryType::BinaryGeneric, data: vec![1] },
+    };
+    let error = doc.get_uuid("java", Standard).unwrap_err();
+    assert_eq!(Some("java".to_string()), error.path);
+    assert!(doc.get_uuid("other", JavaLegacy).is_err());
+
+    let standard = doc.convert_uuids(JavaLegacy, Standard).unwrap();
+    let list = standard
+        .get_document("nested")
+        .unwrap()
+        .get_array("list")
+        .unwrap();
+    assert_eq!(Ok(Binary::from(id)), list.get_binary(0));
+    assert_eq!(Ok(id), list.get_uuid(1, Standard));
+    assert_eq!(Ok(id), standard.get_uuid("java", Standard));
+    let back = standard.convert_uuids(Standard, CSharpLegacy).unwrap();
+    assert_eq!(Ok(id), back.get_uuid("java", CSharpLegacy));
+    assert_eq!(Ok(doc.get_any("other").unwrap()), back.get_any("other"));
+
+    let short =
+        crate::doc! { "a": [Binary { binary_type: BinaryType::BinaryUuid, data: vec![0; 4] }] };
+    let error = short.convert_uuids(Standard, JavaLegacy).unwrap_err();
+    assert_eq!(Some("a.0".to_string()), error.path);
+
+    // {"a": 1, "b": <unknown type 0x55>}, nothing after the corrupt element may be dropped
+    let corrupt = Document {
+        data: vec![0x10, b'a', 0, 1, 0, 0, 0, 0x55, b'b', 0, 2, 0, 0, 0],
+    };
+    let error = corrupt.convert_uuids(Standard, JavaLegacy).unwrap_err();
+    assert_eq!(ErrorKind::UnknownType, error.kind);
+    assert_eq!(Some("b".to_string()), error.path);
+
+    let (v4, v7) = (Uuid::new_v4(), Uuid::new_v7());
+    assert_eq!((4, 7), (v4.version(), v7.version()));
+    assert_eq!(0x80, v4.bytes[8] & 0xC0);
+    assert_ne!(v4, Uuid::new_v4());
+    let millis = u64::from_be_bytes([
+        0,
+        0,
+        v7.bytes[0],
+        v7.bytes[1],
+        v7.bytes[2],
+        v7.bytes[3],
+        v7.bytes[4],
+        v7.bytes[5],
+    ]);
+    assert!(millis as i64 <= crate::DateTime::now().millis);
+}
+
+#[test]
+fn test_object_id_generation() {
+    use crate::DateTime;
+
+    let ids: Vec<ObjectId> = (0..3).map(|_| ObjectId::new()).collect();
+    assert_eq!(ids[0].id[4..9], ids[2].id[4..9]);
+    let counter = |id: &ObjectId| u32::from_be_bytes([0, id.id[9], id.id[10], id.id[11]]);
+    assert_ne!(counter(&ids[0]), counter(&ids[1]));
+    let now = DateTime::now().millis;
+    assert!((now - ids[0].timestamp().millis) < 60_000);
+
+    let id: ObjectId = "5f5b3e9c0000000000000000".parse().unwrap();
+    assert_eq!(DateTime::from(1_599_815_324_000), id.timestamp());
+    assert_eq!(Ok(id), "5F5B3E9C0000000000000000".parse());
+    assert!("5f5b3e9c00000000000000".parse::<ObjectId>().is_err());
+    assert!("5f5b3e9c00000000000000zz".parse::<ObjectId>().is_err());
+
+    let time = DateTime::from(1_599_815_324_999);
+    assert_eq!(id, ObjectId::min_for_time(time));
+    assert_eq!(
+        "5f5b3e9cffffffffffffffff",
+        ObjectId::max_for_time(time).to_string()
+    );
+    assert!(ObjectId::min_for_time(time) < ObjectId::max_for_time(time));
+    assert_eq!([0; 12], ObjectId::min_for_time(DateTime::from(-1)).id);
+    assert_eq!([0xFF; 12], ObjectId::max_for_time(DateTime::MAX).id);
+}
+
+#[test]
+fn test_regular_expression() {
+    use crate::Regex;
+
+    // {"re": /abc/mix} with the options stored unsorted
+    let mut bytes = b"\x11\x00\x00\x00\x0Bre\x00abc\x00mix\x00\x00".to_vec();
+    let doc = Document::try_from(bytes.as_slice()).unwrap();
+    let keys: Vec<String> = doc.iter().map(|(key, _)| key).collect();
+    assert_eq!(vec!["re".to_string()], keys);
+    // decoded options are kept as read, only built values are sorted
+    let regex = doc.get_regex("re").unwrap();
+    assert_eq!(
+        ("abc", "mix"),
+        (regex.pattern.as_str(), regex.options.as_str())
+    );
+    assert!(doc.get_string("re").is_err());
+    assert_eq!(bytes, crate::doc! { "re": regex }.to_bytes());
+    assert_eq!(
+        &b"abc\x00imx\x00"[..],
+        &crate::doc! { "re": Regex::new("abc", "mix").unwrap() }.to_bytes()[8..16]
+    );
+
+    bytes[12] = b'g';
+    let doc = Document::try_from(bytes.as_slice()).unwrap();
+    let error = doc.get_regex("re").unwrap_err();
+    assert_eq!(ErrorKind::Malformed, error.kind);
+    assert_eq!(Some("re".to_string()), error.path);
+    assert!(Document::validate(&bytes).is_err());
+    let json = r#"{"re": {"$regularExpression": {"pattern": "a", "options": "g"}}}"#;
+    assert!(Document::from_extended_json(json).is_err());
+    assert!(Regex::new("a\0b", "").is_err());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regular_expression_compile() {
+    use crate::Regex;
+
+    let regex = Regex::new("^b son # comment\n$", "xiu")
+        .unwrap()
+        .compile()
+        .unwrap();
+    assert!(regex.is_match("BSON"));
+    let regex = Regex::new("^a.b$", "ms").unwrap().compile().unwrap();
+    assert!(regex.is_match("x\na\nb"));
+    assert!(Regex::new("a(?=b)", "").unwrap().compile().is_err());
+}
+
+#[test]
+fn test_dbpointer() {
+    use crate::{DbPointer, DocumentRef};
+
+    // {"a": DBPointer("b", 56e1fc72e0c917e9c4714161), "n": 1}
+    let mut bytes =
+        hex::decode("210000000C610002000000620056E1FC72E0C917E9C4714161106E000100000000").unwrap();
+    let doc = Document::try_from(bytes.as_slice()).unwrap();
+    let pointer = DbPointer {
+        namespace: "b".to_string(),
+        id: "56e1fc72e0c917e9c4714161".parse().unwrap(),
+    };
+    assert_eq!(Ok(pointer.clone()), doc.get_dbpointer("a"));
+    assert_eq!(Ok(1), doc.get_int32("n"));
+    assert_eq!(
+        Ok("b"),
+        DocumentRef::from(&doc)
+            .get_dbpointer("a")
+            .map(|p| p.namespace)
+    );
+    assert_eq!(
+        bytes,
+        crate::doc! { "a": Element::DbPointer(pointer), "n": 1 }.to_bytes()
+    );
+
+    bytes[12] = 1;
+    let doc = Document::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        Err(ErrorKind::Malformed),
+        doc.get_dbpointer("a").map_err(|e| e.kind)
+    );
+    assert!(Document::validate(&bytes).is_err());
+}
+
+#[test]
+fn test_code_with_scope() {
+    use crate::DocumentRef;
+
+    let value = CodeWithScope {
+        code: "f(x)".to_string(),
+        scope: crate::doc! { "x": 1 },
+    };
+    let bytes = crate::doc! { "a": Element::JavascriptCode(value.clone()), "n": 1 }.to_bytes();
+    let doc = Document::try_from_validated(bytes.as_slice()).unwrap();
+    assert_eq!(Ok(value.clone()), doc.get_code_with_scope("a"));
+    assert_eq!(Ok(1), doc.get_int32("n"));
+    let borrowed = DocumentRef::from(&doc).get_code_with_scope("a").unwrap();
+    assert_eq!("f(x)", borrowed.code);
+    assert_eq!(value, borrowed.to_owned());
+
+    // total length of the field, claiming one byte of the next element
+    let mut longer = bytes.clone();
+    longer[7] += 1;
+    let doc = Document::try_from(longer.as_slice()).unwrap();
+    assert_eq!(
+        Err(ErrorKind::BadLength(longer[7] as i32)),
+        doc.get_code_with_scope("a").map_err(|e| e.kind)
+    );
+    assert!(Document::validate(&longer).is_err());
+
+    // code length running into the scope
+    let mut longer = bytes.clone();
+    longer[11] += 1;
+    let doc = Document::try_from(longer.as_slice()).unwrap();
+    assert_eq!(
+        Err(ErrorKind::Malformed),
+        doc.get_code_with_scope("a").map_err(|e| e.kind)
+    );
+
+    // the scope is validated like any embedded document
+    let mut bad_scope = bytes;
+    bad_scope[24] = 0x42;
+    assert!(Document::try_from(bad_scope.as_slice())
+        .unwrap()
+        .get_code_with_scope("a")
+        .is_ok());
+    assert_eq!(
+        Some("a.x".to_string()),
+        Document::validate(&bad_scope).unwrap_err().path
+    );
+}
+
+#[test]
+fn test_binary_subtypes() {
+    for subtype in [0x08, 0x09, 0x0A, 0x7F, 0x80, 0xC3, 0xFF] {
+        let binary = Binary {
+            binary_type: BinaryType::from(subtype),
+            data: vec![1, 2, 3],
+        };
+        let bytes = crate::doc! { "b": binary.clone() }.to_bytes();
+        assert_eq!(subtype, bytes[11]);
+        let doc = Document::try_from_validated(bytes.as_slice()).unwrap();
+        assert_eq!(Ok(binary), doc.get_binary("b"));
+    }
+    assert_eq!(BinaryType::BinaryReserved(0x0A), BinaryType::from(0x0A));
+    let json = r#"{"b": {"$binary": {"base64": "AQ==", "subType": "2a"}}}"#;
+    let doc = Document::from_extended_json(json).unwrap();
+    assert_eq!(
+        Ok(BinaryType::BinaryReserved(0x2A)),
+        doc.get_binary("b").map(|b| b.binary_type)
+    );
+    assert_eq!(
+        json,
+        doc.to_extended_json(crate::ExtendedJsonMode::Canonical)
+    );
+
+    let secret = Binary {
+        binary_type: BinaryType::BinarySensitive,
+        data: b"hunter2".to_vec(),
+    };
+    let debug = format!("{:?}", Element::Binary(secret));
+    assert!(debug.contains("<7 bytes redacted>"), "{debug}");
+    assert!(!debug.contains("104"), "{debug}");
+
+    // old binary repeats the data length, which is stripped on decode
+    let old = Binary {
+        binary_type: BinaryType::BinaryBinary,
+        data: vec![0xFF, 0xFF],
+    };
+    let bytes = crate::doc! { "x": old.clone() }.to_bytes();
+    assert_eq!(
+        hex::decode("13000000057800060000000202000000FFFF00").unwrap(),
+        bytes
+    );
+    assert_eq!(
+        Ok(old),
+        Document::try_from(bytes.as_slice())
+            .unwrap()
+            .get_binary("x")
+    );
+    let mut bytes = bytes;
+    bytes[12] = 3;
+    assert_eq!(
+        Err(ErrorKind::BadLength(3)),
+        Document::try_from(bytes.as_slice())
+            .unwrap()
+            .get_binary("x")
+            .map_err(|e| e.kind)
+    );
+}
+
+#[test]
+fn test_vector() {
+    use crate::Vector;
+
+    let vectors = [
+        (Vector::Int8(vec![127, -128, 0]), "0300_7F8000"),
+        (Vector::Float32(vec![1.0, -0.5]), "2700_0000803F000000BF"),
+        (
+            Vector::PackedBit {
+                bits: vec![0xFF, 0b1110_0000],
+                padding: 5,
+            },
+            "1005_FFE0",
+        ),
+        (
+            Vector::PackedBit {
+                bits: vec![],
+                padding: 0,
+            },
+            "1000_",
+        ),
+    ];
+    for (vector, data) in vectors {
+        let binary = vector.to_binary().unwrap();
+        assert_eq!(BinaryType::BinaryVector, binary.binary_type);
+        assert_eq!(hex::decode(data.replace('_', "")).unwrap(), binary.data);
+        let array = crate::array![binary];
+        assert_eq!(Ok(vector), array.get_vector(0));
+    }
+
+    for invalid in [
+        "0301_01",
+        "2700_000080",
+        "1008_FF",
+        "1003_",
+        "1003_FF",
+        "11_",
+        "0700",
+    ] {
+        let binary = Binary {
+            binary_type: BinaryType::BinaryVector,
+            data: hex::decode(invalid.replace('_', "")).unwrap(),
+        };
+        assert!(Vector::from_binary(&binary).is_err(), "{invalid}");
+    }
+    let doc =
+        crate::doc! { "v": Binary { binary_type: BinaryType::BinaryGeneric, data: vec![3, 0] } };
+    assert!(doc.get_vector("v").is_err());
+}
+
+#[test]
+fn test_bson_column() {
+    use crate::{DateTime, Timestamp};
+
+    let id: ObjectId = "64b7f1a2c3d4e5f601020304".parse().unwrap();
+    let mut next_id = id;
+    next_id.id[11] += 7;
+    let values = vec![
+        Some(Element::Int32(1)),
+        Some(Element::Int32(2)),
+        None,
+        Some(Element::Int32(-40)),
+        Some(Element::Int64(1 << 62)),
+        Some(Element::Int64(-(1 << 62))),
+        Some(Element::Double(1.5)),
+        Some(Element::Double(f64::NAN)),
+        Some(Element::Timestamp(Timestamp {
+            time: 10,
+            increment: 1,
+        })),
+        Some(Element::Timestamp(Timestamp {
+            time: 11,
+            increment: 1,
+        })),
+        Some(Element::Timestamp(Timestamp {
+            time: 12,
+            increment: 1,
+        })),
+        Some(Element::DateTime(DateTime::from(1_700_000_000_000))),
+        Some(Element::DateTime(DateTime::from(1_700_000_001_000))),
+        Some(Element::ObjectId(id)),
+        Some(Element::ObjectId(next_id)),
+        Some(Element::String("sensor".to_string())),
+        Some(Element::String("sensor".to_string())),
+        None,
+        Some(Element::EmbededDocument(crate::doc! { "a": 1 })),
+        Some(Element::Boolean(true)),
+        Some(Element::Boolean(false)),
+        Some(Element::Null),
+        Some(Element::Null),
+    ];
+    let column = Binary::from_column(values.clone());
+    assert_eq!(BinaryType::BinaryCompressed, column.binary_type);
+    let decoded: Vec<Option<Element>> = column
+        .column_iter()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values.len(), decoded.len());
+    for (value, decoded) in values.iter().zip(&decoded) {
+        match (value, decoded) {
+            (Some(Element::Double(a)), Some(Element::Double(b))) => {
+                assert_eq!(a.to_bits(), b.to_bits())
+            }
+            _ => assert_eq!(value, decoded),
+        }
+    }
+
+    // literal 1, then one block of two zigzag encoded deltas of +1 and a missing value
+    let block: u64 = 12 | (2 << 4) | (2 << 24) | (((1 << 20) - 1) << 44);
+    let mut expected = vec![0x10, 0x00, 1, 0, 0, 0, 0x80];
+    expected.extend_from_slice(&block.to_le_bytes());
+    expected.push(0);
+    let values = [1, 2, 3].map(|v| Some(Element::Int32(v)));
+    let column = Binary::from_column(values.into_iter().chain([None]));
+    assert_eq!(expected, column.data);
+
+    // 1.5 scaled by 10 with deltas of +1 and a run of 120 repeated +1 deltas
+    let mut data = vec![0x01, 0x00];
+    data.extend_from_slice(&1.5f64.to_le_bytes());
+    data.push(0xA1);
+    data.extend_from_slice(&(14u64 | (2 << 4)).to_le_bytes());
+    data.extend_from_slice(&15u64.to_le_bytes());
+    data.push(0);
+    let decoded: Vec<Option<Element>> = crate::ColumnIter::new(&data)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(122, decoded.len());
+    assert_eq!(Some(Element::Double(1.6)), decoded[1]);
+    assert_eq!(Some(Element::Double(13.6)), decoded[121]);
+
+    for invalid in [
+        &data[..data.len() - 1],
+        &data[..12],
+        &[0x80, 0, 0][..],
+        &[0xF0][..],
+    ] {
+        assert!(crate::ColumnIter::new(invalid).any(|value| value.is_err()));
+    }
+    assert!(Binary {
+        binary_type: BinaryType::BinaryGeneric,
+        data: vec![0]
+    }
+    .column_iter()
+    .is_err());
+
+    // strings take 128 bit deltas, "b" is "a" + 1
+    let mut expected = vec![0x02, 0x00, 2, 0, 0, 0, b'a', 0, 0x80];
+    expected.extend_from_slice(&(14u64 | (2 << 4)).to_le_bytes());
+    expected.push(0);
+    let values = ["a", "b"].map(|v| Some(Element::String(v.to_string())));
+    assert_eq!(expected, Binary::from_column(values).data);
+
+    // a delta of 2^62 only fits selector 7, 2^48 after dropping 15 trailing zeros
+    let block: u64 = 7 | (9 << 4) | (15 << 8) | (1 << 60);
+    let mut expected = vec![0x12, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0x80];
+    expected.extend_from_slice(&block.to_le_bytes());
+    expected.push(0);
+    let values = [0, 1 << 62].map(|v| Some(Element::Int64(v)));
+    assert_eq!(expected, Binary::from_column(values).data);
+
+    // selector 7 with nine 2 bit values after 4 bit trailing zero counts, 1 << 3 is the zigzag
+    // encoded +4, then selector 8 holding 3 shifted by 10 nibbles
+    let mut data = vec![0x12, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0x81];
+    data.extend_from_slice(&(7u64 | (1 << 4) | (0x13 << 8) | (0x3F << 14)).to_le_bytes());
+    data.extend_from_slice(&(8u64 | (7 << 4) | (0x3A << 8)).to_le_bytes());
+    data.push(0);
+    let decoded: Vec<Option<Element>> = crate::ColumnIter::new(&data)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut expected = vec![Some(Element::Int64(0)), Some(Element::Int64(4)), None];
+    expected.extend(std::iter::repeat_n(Some(Element::Int64(4)), 7));
+    expected.push(Some(Element::Int64(4 + (3 << 39))));
+    assert_eq!(expected, decoded);
+    for selector in [7u64, 7 | (10 << 4), 8 | (8 << 4)] {
+        let mut invalid = data[..11].to_vec();
+        invalid[10] = 0x80;
+        invalid.extend_from_slice(&selector.to_le_bytes());
+        invalid.push(0);
+        assert!(crate::ColumnIter::new(&invalid).any(|value| value.is_err()));
+    }
+
+    // legacy interleaved mode, each field of the reference object reads its own blocks starting
+    // at the reference value, "a" gets deltas 0 and +1, "b" 0 and a missing value
+    let mut data = vec![0xF0];
+    data.extend(crate::doc! { "a": 1, "b": 5 }.to_bytes());
+    data.push(0x80);
+    data.extend_from_slice(&(13u64 | (2 << 34)).to_le_bytes());
+    data.push(0x80);
+    data.extend_from_slice(&(13u64 | (((1 << 30) - 1) << 34)).to_le_bytes());
+    data.extend_from_slice(&[0x00, 0x10, 0x00, 9, 0, 0, 0, 0x00]);
+    let decoded: Vec<Option<Element>> = crate::ColumnIter::new(&data)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        vec![
+            Some(Element::EmbededDocument(crate::doc! { "a": 1, "b": 5 })),
+            Some(Element::EmbededDocument(crate::doc! { "a": 2 })),
+            Some(Element::Int32(9)),
+        ],
+        decoded
+    );
+    let truncated = [&data[..data.len() - 8], &[0x00]].concat();
+    assert!(crate::ColumnIter::new(&truncated).any(|value| value.is_err()));
+
+    let values = vec![
+        Some(Element::Int32(3)),
+        Some(Element::EmbededDocument(crate::doc! {
+            "t": DateTime::from(1_000), "v": 1.5, "m": { "s": "x", "k": [1, 2] },
+        })),
+        Some(Element::EmbededDocument(crate::doc! {
+            "t": DateTime::from(2_000), "v": 2.5, "m": { "s": "y", "k": [1, 3] },
+        })),
+        None,
+        Some(Element::EmbededDocument(crate::doc! {
+            "t": DateTime::from(3_000), "v": 3, "m": { "s": "y", "k": [1, 3] },
+        })),
+        Some(Element::EmbededDocument(crate::doc! { "other": 1 })),
+        Some(Element::ArrayDocument(crate::array![1, "a"])),
+        Some(Element::ArrayDocument(crate::array![2, "b"])),
+        Some(Element::EmbededDocument(crate::doc! {})),
+        Some(Element::String("sensor-0001".to_string())),
+        Some(Element::String("sensor-0002".to_string())),
+        Some(Element::String("a string longer than 16 bytes".to_string())),
+        Some(Element::String("a string longer than 16 bytes".to_string())),
+        Some(Element::String("\0".to_string())),
+        Some(Element::Binary(Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: vec![0, 0, 0, 1],
+        })),
+        Some(Element::Binary(Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: vec![0, 0, 0, 2],
+        })),
+        Some(Element::Binary(Binary {
+            binary_type: BinaryType::BinaryGeneric,
+            data: vec![0, 0, 2],
+        })),
+        Some(Element::Decimal("1.5".parse().unwrap())),
+        Some(Element::Decimal("1.6".parse().unwrap())),
+    ];
+    let column = Binary::from_column(values.clone());
+    assert_eq!(0xF1, column.data[6]);
+    let decoded: Vec<Option<Element>> = column
+        .column_iter()
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(values, decoded);
+}
+
+#[test]
+fn test_timeseries_bucket() {
+    use crate::DateTime;
+
+    // version 1 bucket as the server writes it, "temp" is missing in the second measurement
+    let bucket = crate::doc! {
+        "_id": ObjectId::min_for_time(DateTime::from(60_000)),
+        "control": {
+            "version": 1,
+            "min": { "t": DateTime::from(60_000), "temp": 20.5 },
+            "max": { "t": DateTime::from(61_000), "temp": 20.5 },
+        },
+        "meta": { "sensor": 7 },
+        "data": {
+            "t": { "0": DateTime::from(60_000), "1": DateTime::from(61_000) },
+            "temp": { "0": 20.5 },
+        },
+    };
+    let measurements = vec![
+        crate::doc! { "t": DateTime::from(60_000), "temp": 20.5, "m": { "sensor": 7 } },
+        crate::doc! { "t": DateTime::from(61_000), "m": { "sensor": 7 } },
+    ];
+    assert_eq!(Ok(measurements.clone()), bucket.unpack_bucket(Some("m")));
+    assert_eq!(
+        Ok(crate::doc! { "t": DateTime::from(61_000) }),
+        bucket.unpack_bucket(None).map(|mut docs| docs.remove(1))
+    );
+    assert_eq!(
+        Ok(bucket.clone()),
+        Document::pack_bucket(&measurements, "t", Some("m"), false)
+    );
+
+    let measurements: Vec<Document> = (0..300)
+        .map(|i| {
+            crate::doc! {
+                "t": DateTime::from(1_000 * i),
+                "value": i % 7 - 3,
+                "status": if i % 50 == 0 { "alert" } else { "ok" },
+            }
+        })
+        .collect();
+    let bucket = Document::pack_bucket(&measurements, "t", None, true).unwrap();
+    let control = bucket.get_document("control").unwrap();
+    assert_eq!(Ok(2), control.get_int32("version"));
+    assert_eq!(
+        Ok(-3),
+        control.get_document("min").unwrap().get_i64("value")
+    );
+    assert_eq!(
+        Ok("ok".to_string()),
+        control.get_document("max").unwrap().get_string("status")
+    );
+    let data = bucket.get_document("data").unwrap();
+    assert_eq!(
+        BinaryType::BinaryCompressed,
+        data.get_binary("t").unwrap().binary_type
+    );
+    assert_eq!(Ok(measurements), bucket.unpack_bucket(None));
+
+    let error = Document::pack_bucket(&[crate::doc! { "t": 1 }], "t", None, true).unwrap_err();
+    assert_eq!(Some("0.t".to_string()), error.path);
+    let bucket = crate::doc! { "control": { "version": 1 }, "data": { "t": { "x": 1 } } };
+    assert!(bucket.unpack_bucket(None).is_err());
+    let bucket = crate::doc! {
+        "control": { "version": 1 },
+        "data": { "t": { "0": 1, "99999999999": 2 } },
+    };
+    let error = bucket.unpack_bucket(None).unwrap_err();
+    assert_eq!(Some("data.t".to_string()), error.path);
+    let bucket = crate::doc! {
+        "control": { "version": 1, "count": 1 },
+        "data": { "t": { "0": 1, "1": 2 } },
+    };
+    assert!(bucket.unpack_bucket(None).is_err());
+    let bucket = crate::doc! { "control": { "version": 2, "count": -1 }, "data": {} };
+    let error = bucket.unpack_bucket(None).unwrap_err();
+    assert_eq!(Some("control.count".to_string()), error.path);
+    let bucket = crate::doc! { "control": { "version": 2, "count": 2_000_000_000 }, "data": {} };
+    let error = bucket.unpack_bucket(None).unwrap_err();
+    assert_eq!(Some("control.count".to_string()), error.path);
+    let bucket = crate::doc! {
+        "control": { "version": 1, "count": 5 },
+        "data": { "t": { "0": 1, "1": 2 } },
+    };
+    assert!(bucket.unpack_bucket(None).is_err());
+    // NaN is the smallest number, integers above 2^53 compare exactly against doubles
+    let values = [
+        Element::Double(1.0),
+        Element::Double(f64::NAN),
+        Element::Int64((1 << 53) + 1),
+        Element::Double((1u64 << 53) as f64),
+        Element::Int64(1 << 53),
+    ];
+    let measurements: Vec<Document> = values
+        .iter()
+        .enumerate()
+        .map(|(t, value)| {
+            let mut builder = DocumentBuilder::new();
+            builder
+                .append("t", &DateTime::from(t as i64).into())
+                .append("v", value);
+            builder.build()
+        })
+        .collect();
+    let bucket = Document::pack_bucket(&measurements, "t", None, false).unwrap();
+    let control = bucket.get_document("control").unwrap();
+    let min = control.get_document("min").unwrap().get_any("v").unwrap();
+    assert!(matches!(min, Element::Double(value) if value.is_nan()));
+    assert_eq!(
+        Ok(Element::Int64((1 << 53) + 1)),
+        control.get_document("max").unwrap().get_any("v")
+    );
+
+    let measurements = [0, 1].map(|t| crate::doc! { "t": DateTime::from(t) });
+    let mut bucket = Document::pack_bucket(&measurements, "t", None, true).unwrap();
+    for count in [1, 3] {
+        bucket
+            .get_document_mut("control")
+            .unwrap()
+            .insert("count", Element::Int32(count))
+            .unwrap();
+        let error = bucket.unpack_bucket(None).unwrap_err();
+        assert_eq!(Some("data.t".to_string()), error.path);
+    }
+}
+
+#[test]
+fn test_numeric_coercion() {
+    use crate::Decimal128;
+
+    let decimal = |s: &str| Element::Decimal(s.parse::<Decimal128>().unwrap());
+    let overflow = |expected| ErrorKind::NumericOverflow { expected };
+    let loss = |expected| ErrorKind::PrecisionLoss { expected };
+    let array = crate::array![
+        Element::Int32(-7),
+        Element::Int64(1 << 40),
+        Element::Double(12.0),
+        Element::Double(0.5),
+        Element::Double(f64::NAN),
+        Element::Double(9.3e18),
+        decimal("1.00E+3"),
+        decimal("0.1"),
+        decimal("1E+30"),
+        decimal("1E+400"),
+        Element::Int64((1 << 53) + 1),
+        Element::String("1".to_string()),
+    ];
+    let i64s: Vec<_> = (0..12)
+        .map(|i| array.get_i64_coerced(i).map_err(|e| e.kind))
+        .collect();
+    assert_eq!(
+        vec![
+            Ok(-7),
+            Ok(1 << 40),
+            Ok(12),
+            Err(loss(0x12)),
+            Err(loss(0x12)),
+            Err(overflow(0x12)),
+            Ok(1000),
+            Err(loss(0x12)),
+            Err(overflow(0x12)),
+            Err(overflow(0x12)),
+            Ok((1 << 53) + 1),
+            Err(ErrorKind::TypeMismatch {
+                expected: 0x12,
+                actual: 0x02
+            }),
+        ],
+        i64s
+    );
+
+    assert_eq!(Ok(-7), array.get_i32_checked(0));
+    assert_eq!(
+        Err(overflow(0x10)),
+        array.get_i32_checked(1).map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Err(loss(0x10)),
+        array.get_i32_checked(3).map_err(|e| e.kind)
+    );
+    assert_eq!(Ok(1000), array.get_i32_checked(6));
+
+    assert_eq!(
+        Err(loss(0x12)),
+        crate::array![decimal("123456789012345678.5")]
+            .get_i64_coerced(0)
+            .map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Ok(i64::MIN),
+        crate::array![decimal("-9223372036854775808")].get_i64_coerced(0)
+    );
+    assert_eq!(
+        Err(overflow(0x12)),
+        crate::array![decimal("9223372036854775807.5")]
+            .get_i64_coerced(0)
+            .map_err(|e| e.kind)
+    );
+
+    for value in [
+        Element::Double(f64::INFINITY),
+        Element::Double(f64::NEG_INFINITY),
+        decimal("Infinity"),
+        decimal("-Infinity"),
+    ] {
+        let array = crate::array![value];
+        assert_eq!(
+            Err(overflow(0x12)),
+            array.get_i64_coerced(0).map_err(|e| e.kind)
+        );
+        assert_eq!(
+            Err(overflow(0x10)),
+            array.get_i32_checked(0).map_err(|e| e.kind)
+        );
+    }
+
+    assert_eq!(Ok(1099511627776.0), array.get_f64_coerced(1));
+    assert_eq!(Ok(0.1), array.get_f64_coerced(7));
+    assert_eq!(Ok(1e30), array.get_f64_coerced(8));
+    assert_eq!(
+        Err(overflow(0x01)),
+        array.get_f64_coerced(9).map_err(|e| e.kind)
+    );
+    assert_eq!(
+        Err(loss(0x01)),
+        array.get_f64_coerced(10).map_err(|e| e.kind)
+    );
+
+    let error = crate::doc! { "n": 0.5 }.get_i32_checked("n").unwrap_err();
+    assert_eq!(Some("n".to_string()), error.path);
+    assert_eq!(Some(0x01), error.element_type);
+}
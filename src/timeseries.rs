@@ -0,0 +1,331 @@
+use std::cmp::Ordering;
+
+use super::builder::*;
+use super::datetime::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+
+/// rank of each type in the server's comparison order, numbers share one rank
+fn type_rank(element: &Element) -> u8 {
+    match element {
+        Element::Min => 0,
+        Element::Null | Element::Undefined => 1,
+        Element::Double(_) | Element::Int32(_) | Element::Int64(_) | Element::Decimal(_) => 2,
+        Element::String(_) | Element::Symbol(_) => 3,
+        Element::EmbededDocument(_) => 4,
+        Element::ArrayDocument(_) => 5,
+        Element::Binary(_) => 6,
+        Element::ObjectId(_) => 7,
+        Element::Boolean(_) => 8,
+        Element::DateTime(_) => 9,
+        Element::Timestamp(_) => 10,
+        Element::RegularExpression { .. } => 11,
+        Element::DbPointer(_) => 12,
+        Element::Javascript(_) => 13,
+        Element::JavascriptCode(_) => 14,
+        Element::Max => 15,
+    }
+}
+
+/// a number in the form it compares exactly in, NaN first
+enum Number {
+    NaN,
+    Int(i64),
+    Float(f64),
+}
+
+fn number(element: &Element) -> Number {
+    match element {
+        Element::Double(value) if value.is_nan() => Number::NaN,
+        Element::Double(value) => Number::Float(*value),
+        Element::Int32(value) => Number::Int(*value as i64),
+        Element::Int64(value) => Number::Int(*value),
+        Element::Decimal(value) if value.is_nan() => Number::NaN,
+        Element::Decimal(value) => match i64::try_from(*value) {
+            Ok(value) => Number::Int(value),
+            Err(_) => Number::Float(value.to_f64()),
+        },
+        _ => Number::NaN,
+    }
+}
+
+/// orders an integer against a double without rounding either
+fn compare_int_float(int: i64, float: f64) -> Ordering {
+    // 2^63, the first double past `i64::MAX`
+    let limit = -(i64::MIN as f64);
+    if float >= limit {
+        Ordering::Less
+    } else if float < -limit {
+        Ordering::Greater
+    } else {
+        let whole = float.trunc();
+        int.cmp(&(whole as i64))
+            .then(whole.partial_cmp(&float).expect("not NaN"))
+    }
+}
+
+/// numbers by value across types, NaN below every other number as the server orders it
+fn compare_numbers(a: Number, b: Number) -> Ordering {
+    match (a, b) {
+        (Number::NaN, Number::NaN) => Ordering::Equal,
+        (Number::NaN, _) => Ordering::Less,
+        (_, Number::NaN) => Ordering::Greater,
+        (Number::Int(a), Number::Int(b)) => a.cmp(&b),
+        (Number::Float(a), Number::Float(b)) => a.partial_cmp(&b).expect("not NaN"),
+        (Number::Int(a), Number::Float(b)) => compare_int_float(a, b),
+        (Number::Float(a), Number::Int(b)) => compare_int_float(b, a).reverse(),
+    }
+}
+
+/// orders values for `control.min` and `control.max`, documents, arrays and other composite
+/// values compare by their encoding rather than field by field
+fn compare_elements(a: &Element, b: &Element) -> Ordering {
+    let rank = type_rank(a).cmp(&type_rank(b));
+    if rank != Ordering::Equal {
+        return rank;
+    }
+    match (a, b) {
+        (Element::String(a) | Element::Symbol(a), Element::String(b) | Element::Symbol(b)) => {
+            a.cmp(b)
+        }
+        (Element::ObjectId(a), Element::ObjectId(b)) => a.cmp(b),
+        (Element::Boolean(a), Element::Boolean(b)) => a.cmp(b),
+        (Element::DateTime(a), Element::DateTime(b)) => a.cmp(b),
+        (Element::Timestamp(a), Element::Timestamp(b)) => a.cmp(b),
+        _ if type_rank(a) == 2 => compare_numbers(number(a), number(b)),
+        _ => {
+            let (mut left, mut right) = (vec![], vec![]);
+            a.encode_into(&mut left);
+            b.encode_into(&mut right);
+            left.cmp(&right)
+        }
+    }
+}
+
+fn optional(result: Result<Element, BsonError>) -> Result<Option<Element>, BsonError> {
+    match result {
+        Ok(element) => Ok(Some(element)),
+        Err(error) if error.kind == ErrorKind::KeyNotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn bucket_error(message: String, key: &str) -> BsonError {
+    BsonError::custom_message(message).with_key(key)
+}
+
+/// values of one `data` field by row, from indexed sub documents or a BSON Column, row indices
+/// of sub documents must be below `limit` and a BSON Column must hold `count` rows when given
+fn read_column(
+    key: &str,
+    column: Element,
+    limit: usize,
+    count: Option<usize>,
+) -> Result<Vec<Option<Element>>, BsonError> {
+    match column {
+        Element::EmbededDocument(rows) => {
+            let mut values = vec![];
+            for (index, value) in rows.iter() {
+                let row: usize = index.parse().map_err(|_| {
+                    bucket_error(format!("row index {index:?} is not a number"), key)
+                })?;
+                if row >= limit {
+                    return Err(bucket_error(
+                        format!("row index {row} is out of range, the bucket has {limit} rows"),
+                        key,
+                    ));
+                }
+                if values.len() <= row {
+                    values.resize(row + 1, None);
+                }
+                values[row] = Some(value);
+            }
+            Ok(values)
+        }
+        Element::Binary(binary) => {
+            let iter = binary.column_iter().map_err(|error| error.nested(key, 0))?;
+            // one value past `count` is enough to tell the column is too long
+            let values: Vec<Option<Element>> = iter
+                .take(count.map_or(usize::MAX, |count| count.saturating_add(1)))
+                .collect::<Result<_, _>>()
+                .map_err(|error| error.nested(key, 0))?;
+            match count {
+                Some(count) if values.len() != count => Err(bucket_error(
+                    format!("column has {} rows, control.count is {count}", values.len()),
+                    key,
+                )),
+                _ => Ok(values),
+            }
+        }
+        other => Err(
+            BsonError::type_mismatch(ELEMENT_TYPE_EMBED_DOCUMENT, other.element_type())
+                .with_key(key),
+        ),
+    }
+}
+
+impl Document {
+    /// Reconstructs the measurements of a time-series bucket (`system.buckets.*` document)
+    ///
+    /// Columns of `data` are either documents keyed by row index (version 1) or BSON Column
+    /// binaries (version 2). The bucket does not record the name of the meta field, pass it as
+    /// `meta_field` to copy `meta` into every measurement, after the data fields.
+    ///
+    /// ```rust
+    /// use bson2::{doc, DateTime, Document};
+    ///
+    /// let measurements = vec![
+    ///     doc! { "t": DateTime::from(1000), "temp": 20, "tag": "a" },
+    ///     doc! { "t": DateTime::from(2000), "temp": 21, "tag": "a" },
+    /// ];
+    /// let bucket = Document::pack_bucket(&measurements, "t", Some("tag"), true).unwrap();
+    /// assert_eq!(Ok(2), bucket.get_document("control").unwrap().get_int32("count"));
+    /// assert_eq!(Ok(measurements), bucket.unpack_bucket(Some("tag")));
+    /// ```
+    pub fn unpack_bucket(&self, meta_field: Option<&str>) -> Result<Vec<Document>, BsonError> {
+        let count = match optional(self.get_document("control")?.get_any("count"))? {
+            Some(count) => {
+                let count = count
+                    .as_int32()
+                    .map_err(|error| error.with_key("control.count"))?;
+                Some(usize::try_from(count).map_err(|_| {
+                    bucket_error(format!("negative count {count}"), "control.count")
+                })?)
+            }
+            None => None,
+        };
+        let data = self.get_document("data")?;
+        // without a count, the longest indexed column has a row for every measurement
+        let limit = count.unwrap_or_else(|| {
+            data.iter()
+                .map(|(_, column)| match column {
+                    Element::EmbededDocument(rows) => rows.iter().count(),
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0)
+        });
+        let mut columns = vec![];
+        for (key, column) in data.iter() {
+            let values =
+                read_column(&key, column, limit, count).map_err(|error| error.nested("data", 0))?;
+            columns.push((key, values));
+        }
+        let rows = columns
+            .iter()
+            .map(|(_, values)| values.len())
+            .max()
+            .unwrap_or(0);
+        if count.is_some_and(|count| count != rows) {
+            return Err(bucket_error(
+                format!("control.count does not match the {rows} rows of data"),
+                "control.count",
+            ));
+        }
+        let meta = match meta_field {
+            Some(field) => optional(self.get_any("meta"))?.map(|meta| (field, meta)),
+            None => None,
+        };
+
+        let mut measurements = vec![];
+        for row in 0..rows {
+            let mut builder = DocumentBuilder::new();
+            for (key, values) in &columns {
+                if let Some(Some(value)) = values.get(row) {
+                    builder.append(key, value);
+                }
+            }
+            if let Some((field, meta)) = &meta {
+                builder.append(field, meta);
+            }
+            measurements.push(builder.build());
+        }
+        Ok(measurements)
+    }
+
+    /// Builds the bucket holding `measurements`, mostly to produce test data for
+    /// [`Document::unpack_bucket`]
+    ///
+    /// Every measurement needs a date in `time_field`, the `meta_field` value must be the same in
+    /// all of them. `compressed` stores the columns as BSON Column binaries (version 2) instead of
+    /// indexed documents (version 1). The `_id` is the smallest ObjectId of the earliest time.
+    pub fn pack_bucket(
+        measurements: &[Document],
+        time_field: &str,
+        meta_field: Option<&str>,
+        compressed: bool,
+    ) -> Result<Document, BsonError> {
+        let mut fields: Vec<String> = vec![];
+        let mut meta: Option<Element> = None;
+        let mut min_time = None;
+        for (index, measurement) in measurements.iter().enumerate() {
+            let time = measurement
+                .get_datetime(time_field)
+                .map_err(|error| error.nested(&index.to_string(), 0))?;
+            min_time = Some(min_time.map_or(time, |min: DateTime| min.min(time)));
+            for (key, value) in measurement.iter() {
+                if Some(key.as_str()) != meta_field {
+                    if !fields.contains(&key) {
+                        fields.push(key);
+                    }
+                } else if meta.get_or_insert_with(|| value.clone()) != &value {
+                    return Err(bucket_error(
+                        "measurements have different meta values".to_string(),
+                        &format!("{index}.{key}"),
+                    ));
+                }
+            }
+        }
+        let min_time = min_time
+            .ok_or_else(|| BsonError::custom_message("a bucket needs measurements".to_string()))?;
+
+        let (mut min, mut max, mut data) = (
+            DocumentBuilder::new(),
+            DocumentBuilder::new(),
+            DocumentBuilder::new(),
+        );
+        for field in &fields {
+            let values: Vec<Option<Element>> = measurements
+                .iter()
+                .map(|measurement| optional(measurement.get_any(field)))
+                .collect::<Result<_, _>>()?;
+            let present = values.iter().flatten();
+            if let Some(value) = present.clone().min_by(|a, b| compare_elements(a, b)) {
+                min.append(field, value);
+            }
+            if let Some(value) = present.max_by(|a, b| compare_elements(a, b)) {
+                max.append(field, value);
+            }
+            if compressed {
+                data.append(field, &Binary::from_column(values).into());
+            } else {
+                let mut rows = DocumentBuilder::new();
+                for (row, value) in values.iter().enumerate() {
+                    if let Some(value) = value {
+                        rows.append(&row.to_string(), value);
+                    }
+                }
+                data.append(field, &rows.build().into());
+            }
+        }
+
+        let mut control = DocumentBuilder::new();
+        control
+            .append("version", &Element::Int32(if compressed { 2 } else { 1 }))
+            .append("min", &min.build().into())
+            .append("max", &max.build().into());
+        if compressed {
+            control.append("count", &Element::Int32(measurements.len() as i32));
+        }
+        let mut bucket = DocumentBuilder::new();
+        bucket
+            .append("_id", &ObjectId::min_for_time(min_time).into())
+            .append("control", &control.build().into());
+        if let Some(meta) = &meta {
+            bucket.append("meta", meta);
+        }
+        bucket.append("data", &data.build().into());
+        Ok(bucket.build())
+    }
+}
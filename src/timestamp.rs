@@ -0,0 +1,48 @@
+use super::element::*;
+
+/// BSON replication timestamp, seconds since the unix epoch and an ordinal within that second
+///
+/// Orders like the server does, by `time` first and `increment` second, which is the order of
+/// the unsigned 64 bit value stored in BSON.
+///
+/// ```rust
+/// use bson2::{doc, Timestamp};
+///
+/// let doc = doc! { "ts": Timestamp { time: 1_700_000_000, increment: 3 } };
+/// let ts = doc.get_timestamp("ts").unwrap();
+/// assert_eq!(3, ts.increment);
+/// assert!(ts < Timestamp { time: 1_700_000_001, increment: 0 });
+/// assert_eq!("Timestamp(1700000000, 3)", ts.to_string());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    pub time: u32,
+    pub increment: u32,
+}
+
+impl From<u64> for Timestamp {
+    fn from(value: u64) -> Self {
+        Self {
+            time: (value >> 32) as u32,
+            increment: value as u32,
+        }
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(value: Timestamp) -> Self {
+        ((value.time as u64) << 32) | value.increment as u64
+    }
+}
+
+impl From<Timestamp> for Element {
+    fn from(value: Timestamp) -> Self {
+        Element::Timestamp(value)
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Timestamp({}, {})", self.time, self.increment)
+    }
+}
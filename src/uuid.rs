@@ -0,0 +1,261 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::builder::*;
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+
+/// 128 bit UUID, `bytes` are in the RFC 4122 order of the hyphenated string
+///
+/// ```rust
+/// use bson2::{doc, Uuid, UuidRepresentation};
+///
+/// let id: Uuid = "00112233-4455-6677-8899-aabbccddeeff".parse().unwrap();
+/// let doc = doc! { "standard": id, "java": id.to_binary(UuidRepresentation::JavaLegacy) };
+/// assert_eq!(Ok(id), doc.get_uuid("standard", UuidRepresentation::Standard));
+/// assert_eq!(Ok(id), doc.get_uuid("java", UuidRepresentation::JavaLegacy));
+/// assert_eq!(
+///     [0x77, 0x66, 0x55, 0x44],
+///     doc.get_binary("java").unwrap().data[..4]
+/// );
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid {
+    pub bytes: [u8; 16],
+}
+
+/// How a UUID is laid out in a binary element
+///
+/// New data uses `Standard` (subtype 4). The legacy variants are the subtype 3 byte orders that
+/// older drivers wrote, which one applies depends on the driver that stored the value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UuidRepresentation {
+    /// subtype 4, bytes in RFC 4122 order
+    Standard,
+    /// subtype 3, each 8 byte half reversed, written by the legacy Java driver
+    JavaLegacy,
+    /// subtype 3, the first three fields little endian, written by the legacy C# driver
+    CSharpLegacy,
+    /// subtype 3, bytes in RFC 4122 order, written by the legacy Python driver
+    PythonLegacy,
+}
+
+impl UuidRepresentation {
+    fn binary_type(self) -> BinaryType {
+        match self {
+            UuidRepresentation::Standard => BinaryType::BinaryUuid,
+            _ => BinaryType::BinaryOldUuid,
+        }
+    }
+
+    /// converts between RFC 4122 order and this representation, each reordering is its own
+    /// inverse
+    fn reorder(self, mut bytes: [u8; 16]) -> [u8; 16] {
+        match self {
+            UuidRepresentation::Standard | UuidRepresentation::PythonLegacy => {}
+            UuidRepresentation::JavaLegacy => {
+                bytes[..8].reverse();
+                bytes[8..].reverse();
+            }
+            UuidRepresentation::CSharpLegacy => {
+                bytes[..4].reverse();
+                bytes[4..6].reverse();
+                bytes[6..8].reverse();
+            }
+        }
+        bytes
+    }
+}
+
+pub(crate) fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).expect("operating system random number generator failed");
+    bytes
+}
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self { bytes }
+    }
+
+    /// random UUID, version 4
+    pub fn new_v4() -> Self {
+        Self::from_bytes(random_bytes()).with_version(4)
+    }
+
+    /// UUID starting with the current unix time in milliseconds, version 7, so ids created later
+    /// sort after earlier ones
+    pub fn new_v7() -> Self {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let mut bytes: [u8; 16] = random_bytes();
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        Self::from_bytes(bytes).with_version(7)
+    }
+
+    fn with_version(mut self, version: u8) -> Self {
+        self.bytes[6] = (self.bytes[6] & 0x0F) | (version << 4);
+        self.bytes[8] = (self.bytes[8] & 0x3F) | 0x80;
+        self
+    }
+
+    /// the version nibble, 4 for random and 7 for time ordered UUIDs
+    pub fn version(&self) -> u8 {
+        self.bytes[6] >> 4
+    }
+
+    /// binary element holding this UUID in the given representation
+    pub fn to_binary(&self, representation: UuidRepresentation) -> Binary {
+        Binary {
+            binary_type: representation.binary_type(),
+            data: representation.reorder(self.bytes).to_vec(),
+        }
+    }
+
+    /// reads a subtype 4 binary, or a subtype 3 binary written in the legacy `representation`
+    pub fn from_binary(
+        binary: &Binary,
+        representation: UuidRepresentation,
+    ) -> Result<Uuid, BsonError> {
+        let bytes: [u8; 16] = binary.data.as_slice().try_into().map_err(|_| {
+            BsonError::custom_message(format!("uuid needs 16 bytes, found {}", binary.data.len()))
+        })?;
+        match (binary.binary_type, representation) {
+            (BinaryType::BinaryUuid, _) => Ok(Uuid::from_bytes(bytes)),
+            (BinaryType::BinaryOldUuid, UuidRepresentation::Standard) => {
+                Err(BsonError::custom_message(
+                    "legacy uuid (subtype 3) needs a legacy representation".to_string(),
+                ))
+            }
+            (BinaryType::BinaryOldUuid, _) => Ok(Uuid::from_bytes(representation.reorder(bytes))),
+            (other, _) => Err(BsonError::custom_message(format!(
+                "binary subtype 0x{:02x} is not a uuid",
+                u8::from(&other)
+            ))),
+        }
+    }
+}
+
+impl std::fmt::Display for Uuid {
+    /// lowercase hyphenated form, `00112233-4455-6677-8899-aabbccddeeff`
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let hex = hex::encode(self.bytes);
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+impl std::fmt::Debug for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Uuid").field(&self.to_string()).finish()
+    }
+}
+
+impl std::str::FromStr for Uuid {
+    type Err = BsonError;
+
+    /// accepts the hyphenated form and 32 plain hex digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hyphenated = s.len() == 36
+            && s.char_indices()
+                .all(|(i, c)| (c == '-') == [8, 13, 18, 23].contains(&i));
+        let digits = if hyphenated {
+            s.replace('-', "")
+        } else {
+            s.to_string()
+        };
+        let mut bytes = [0u8; 16];
+        hex::decode_to_slice(digits, &mut bytes)
+            .map_err(|_| BsonError::custom_message(format!("invalid uuid {s:?}")))?;
+        Ok(Uuid::from_bytes(bytes))
+    }
+}
+
+impl From<Uuid> for Binary {
+    fn from(value: Uuid) -> Self {
+        value.to_binary(UuidRepresentation::Standard)
+    }
+}
+
+impl From<Uuid> for Element {
+    fn from(value: Uuid) -> Self {
+        Element::Binary(value.into())
+    }
+}
+
+impl Element {
+    pub fn as_uuid(self, representation: UuidRepresentation) -> Result<Uuid, BsonError> {
+        Uuid::from_binary(&self.as_binary()?, representation)
+    }
+}
+
+impl Document {
+    pub fn get_uuid(
+        &self,
+        key: &str,
+        representation: UuidRepresentation,
+    ) -> Result<Uuid, BsonError> {
+        Uuid::from_binary(&self.get_binary(key)?, representation)
+            .map_err(|error| error.with_key(key))
+    }
+
+    /// Rewrites every UUID stored as `from` into `to`, in nested documents and arrays as well,
+    /// typically to move legacy subtype 3 values to the standard subtype 4 or back
+    ///
+    /// Binaries of the other UUID subtype are copied unchanged, a binary of the `from` subtype
+    /// that is not 16 bytes long is an error.
+    pub fn convert_uuids(
+        &self,
+        from: UuidRepresentation,
+        to: UuidRepresentation,
+    ) -> Result<Document, BsonError> {
+        let mut builder = DocumentBuilder::new();
+        // walked with the fallible parser, `iter` would stop quietly at a corrupt element
+        let mut input = self.data.as_slice();
+        while !input.is_empty() {
+            let (rest, (key, element)) = decode_element(&self.data, input)?;
+            let element = convert_uuid_element(element.to_owned(), from, to)
+                .map_err(|error| error.nested(key, 0))?;
+            builder.append(key, &element);
+            input = rest;
+        }
+        Ok(builder.build())
+    }
+}
+
+impl Array {
+    pub fn get_uuid(
+        &self,
+        key: usize,
+        representation: UuidRepresentation,
+    ) -> Result<Uuid, BsonError> {
+        Uuid::from_binary(&self.get_binary(key)?, representation)
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+}
+
+fn convert_uuid_element(
+    element: Element,
+    from: UuidRepresentation,
+    to: UuidRepresentation,
+) -> Result<Element, BsonError> {
+    Ok(match element {
+        Element::Binary(binary) if binary.binary_type == from.binary_type() => {
+            Element::Binary(Uuid::from_binary(&binary, from)?.to_binary(to))
+        }
+        Element::EmbededDocument(doc) => Element::EmbededDocument(doc.convert_uuids(from, to)?),
+        Element::ArrayDocument(array) => {
+            let doc = Document { data: array.data }.convert_uuids(from, to)?;
+            Element::ArrayDocument(Array { data: doc.data })
+        }
+        other => other,
+    })
+}
@@ -0,0 +1,107 @@
+use super::element::*;
+use super::error::*;
+use super::parse::*;
+
+/// deepest nesting of documents and arrays accepted, the server's own limit
+pub const MAX_NESTING_DEPTH: usize = 200;
+
+fn error_at(kind: ErrorKind, offset: usize) -> BsonError {
+    BsonError::new(kind).with_offset(offset)
+}
+
+/// checks one encoded document (length prefix, elements and trailing NUL), `bytes` must hold
+/// exactly the length it declares
+fn validate_document(bytes: &[u8], array: bool, depth: usize) -> Result<(), BsonError> {
+    if depth > MAX_NESTING_DEPTH {
+        return Err(error_at(ErrorKind::NestingTooDeep, 0));
+    }
+    let (_, size) = parse_document_length(bytes).map_err(|err| parse_error(bytes, err, 0))?;
+    if size > bytes.len() {
+        return Err(error_at(ErrorKind::Truncated, bytes.len()));
+    }
+    if size < bytes.len() {
+        return Err(error_at(ErrorKind::BadLength(size as i32), 0));
+    }
+    if bytes[size - 1] != 0 {
+        return Err(error_at(ErrorKind::Malformed, size - 1));
+    }
+
+    let body = &bytes[4..size - 1];
+    let mut input = body;
+    let mut index = 0usize;
+    while !input.is_empty() {
+        let start = body.len() - input.len();
+        let element_type = input[0];
+        let (rest, _) = decode_element(body, input)?;
+        let end = body.len() - rest.len();
+        // keys are read from the bytes, not from the parsed element
+        let (_, key) = parse_estring(&input[1..]).map_err(|err| parse_error(body, err, 4))?;
+        let value_start = start + key.len() + 2;
+        let value = &body[value_start..end];
+        let element_error = |kind: ErrorKind, offset: usize| {
+            BsonError::new(kind)
+                .with_offset(offset + 4)
+                .with_key(key)
+                .with_element_type(element_type)
+        };
+
+        if array && key != index.to_string() {
+            return Err(element_error(ErrorKind::BadArrayKey, start + 1));
+        }
+        match element_type {
+            ELEMENT_TYPE_STRING | ELEMENT_TYPE_JAVASCRIPTCODE | ELEMENT_TYPE_SYMBOL
+                if value.last() != Some(&0) =>
+            {
+                return Err(element_error(ErrorKind::Malformed, end - 1));
+            }
+            ELEMENT_TYPE_EMBED_DOCUMENT | ELEMENT_TYPE_ARRAY_DOCUMENT => {
+                let nested_array = element_type == ELEMENT_TYPE_ARRAY_DOCUMENT;
+                validate_document(value, nested_array, depth + 1)
+                    .map_err(|error| error.nested(key, value_start + 4))?;
+            }
+            ELEMENT_TYPE_JAVASCRIPTCODEWITHSCOPE => {
+                // the parser already checked the lengths, the scope starts after the code
+                let code_size = i32::from_le_bytes([value[4], value[5], value[6], value[7]]);
+                let scope_start = 8 + code_size as usize;
+                validate_document(&value[scope_start..], false, depth + 1)
+                    .map_err(|error| error.nested(key, value_start + scope_start + 4))?;
+            }
+            ELEMENT_TYPE_BOOLEAN if value[0] > 1 => {
+                return Err(element_error(ErrorKind::Malformed, value_start));
+            }
+            _ => {}
+        }
+        input = rest;
+        index += 1;
+    }
+    Ok(())
+}
+
+impl Document {
+    /// Walks the whole encoded document once and rejects anything malformed: lengths that
+    /// disagree with their content or with `input`, missing NUL terminators, invalid UTF-8,
+    /// unknown element types, out of order array keys and too deep nesting.
+    ///
+    /// ```rust
+    /// use bson2::{doc, Document, ErrorKind};
+    ///
+    /// let mut bytes = doc! { "list": [1, 2] }.to_bytes();
+    /// assert!(Document::validate(&bytes).is_ok());
+    ///
+    /// // rename the second array key from "1" to "2"
+    /// let key = bytes.len() - 8;
+    /// bytes[key] = b'2';
+    /// let error = Document::validate(&bytes).unwrap_err();
+    /// assert_eq!(ErrorKind::BadArrayKey, error.kind);
+    /// assert_eq!(Some("list.2".to_string()), error.path);
+    /// ```
+    pub fn validate(input: &[u8]) -> Result<(), BsonError> {
+        validate_document(input, false, 0)
+    }
+
+    /// like `Document::try_from` after a successful [`Document::validate`]
+    pub fn try_from_validated(input: &[u8]) -> Result<Document, BsonError> {
+        Document::validate(input)?;
+        Document::try_from(input)
+    }
+}
@@ -0,0 +1,137 @@
+use super::element::*;
+use super::error::*;
+
+const DTYPE_INT8: u8 = 0x03;
+const DTYPE_FLOAT32: u8 = 0x27;
+const DTYPE_PACKED_BIT: u8 = 0x10;
+
+/// Dense vector stored in a binary of subtype 0x09, as used by vector search
+///
+/// The binary data starts with a dtype byte and a padding byte. `padding` of a `PackedBit` vector
+/// is the number of unused low bits in the last byte, those bits must be zero.
+///
+/// ```rust
+/// use bson2::{doc, Vector};
+///
+/// let vector = Vector::Float32(vec![0.5, -1.0]);
+/// let doc = doc! { "embedding": vector.to_binary().unwrap() };
+/// assert_eq!(Ok(vector), doc.get_vector("embedding"));
+///
+/// let bits = Vector::PackedBit { bits: vec![0b1010_0000], padding: 5 };
+/// assert!(bits.to_binary().is_ok());
+/// assert!(Vector::PackedBit { bits: vec![0b1010_1000], padding: 5 }.to_binary().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vector {
+    Int8(Vec<i8>),
+    Float32(Vec<f32>),
+    PackedBit { bits: Vec<u8>, padding: u8 },
+}
+
+fn vector_error(message: &str) -> BsonError {
+    BsonError::custom_message(format!("invalid vector: {message}"))
+}
+
+fn check_padding(bits: &[u8], padding: u8) -> Result<(), BsonError> {
+    if padding > 7 {
+        return Err(vector_error("packed bit padding must be between 0 and 7"));
+    }
+    match bits.last() {
+        None if padding > 0 => Err(vector_error("padding of an empty packed bit vector")),
+        Some(last) if last & ((1u8 << padding) - 1) != 0 => Err(vector_error(
+            "padding bits of a packed bit vector must be zero",
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl Vector {
+    /// binary element of subtype 0x09 holding this vector, fails on invalid packed bit padding
+    pub fn to_binary(&self) -> Result<Binary, BsonError> {
+        let data = match self {
+            Vector::Int8(values) => {
+                let mut data = vec![DTYPE_INT8, 0];
+                data.extend(values.iter().map(|value| *value as u8));
+                data
+            }
+            Vector::Float32(values) => {
+                let mut data = vec![DTYPE_FLOAT32, 0];
+                data.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+                data
+            }
+            Vector::PackedBit { bits, padding } => {
+                check_padding(bits, *padding)?;
+                let mut data = vec![DTYPE_PACKED_BIT, *padding];
+                data.extend_from_slice(bits);
+                data
+            }
+        };
+        Ok(Binary {
+            binary_type: BinaryType::BinaryVector,
+            data,
+        })
+    }
+
+    pub fn from_binary(binary: &Binary) -> Result<Vector, BsonError> {
+        if binary.binary_type != BinaryType::BinaryVector {
+            return Err(vector_error(&format!(
+                "binary subtype 0x{:02x} is not a vector",
+                u8::from(&binary.binary_type)
+            )));
+        }
+        let (dtype, padding, values) = match binary.data.as_slice() {
+            [dtype, padding, values @ ..] => (*dtype, *padding, values),
+            _ => return Err(vector_error("missing dtype and padding bytes")),
+        };
+        if dtype != DTYPE_PACKED_BIT && padding != 0 {
+            return Err(vector_error("only packed bit vectors have padding"));
+        }
+        match dtype {
+            DTYPE_INT8 => Ok(Vector::Int8(
+                values.iter().map(|value| *value as i8).collect(),
+            )),
+            DTYPE_FLOAT32 if values.len() % 4 == 0 => Ok(Vector::Float32(
+                values
+                    .chunks_exact(4)
+                    .map(|value| f32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+                    .collect(),
+            )),
+            DTYPE_FLOAT32 => Err(vector_error("float32 data is not a multiple of 4 bytes")),
+            DTYPE_PACKED_BIT => {
+                check_padding(values, padding)?;
+                Ok(Vector::PackedBit {
+                    bits: values.to_vec(),
+                    padding,
+                })
+            }
+            other => Err(vector_error(&format!("unknown dtype 0x{other:02x}"))),
+        }
+    }
+}
+
+impl TryFrom<Vector> for Binary {
+    type Error = BsonError;
+
+    fn try_from(value: Vector) -> Result<Self, Self::Error> {
+        value.to_binary()
+    }
+}
+
+impl Element {
+    pub fn as_vector(self) -> Result<Vector, BsonError> {
+        Vector::from_binary(&self.as_binary()?)
+    }
+}
+
+impl Document {
+    pub fn get_vector(&self, key: &str) -> Result<Vector, BsonError> {
+        Vector::from_binary(&self.get_binary(key)?).map_err(|error| error.with_key(key))
+    }
+}
+
+impl Array {
+    pub fn get_vector(&self, key: usize) -> Result<Vector, BsonError> {
+        Vector::from_binary(&self.get_binary(key)?)
+            .map_err(|error| error.with_key(&key.to_string()))
+    }
+}